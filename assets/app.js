@@ -0,0 +1,158 @@
+// Talks to the REST API in src/server.rs, all game rules stay on the server
+let game = null;
+let heatmap = null;      // safety grid from /analysis, cleared after every move
+let memoMode = false;
+let memoValue = "V";
+
+async function api(method, path, body) {
+  const response = await fetch(path, {
+    method,
+    headers: { "Content-Type": "application/json" },
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  const data = await response.json();
+  if (!response.ok) {
+    throw new Error(data.error);
+  }
+  return data;
+}
+
+async function newGame() {
+  const request = { size: Number(document.getElementById("size").value) };
+  const level = document.getElementById("level").value;
+  const seed = document.getElementById("seed").value;
+  if (level) request.level = Number(level);
+  if (seed) request.seed = Number(seed);
+
+  if (game && game.result === "in_progress") {
+    api("POST", `/games/${game.id}/quit`).catch(() => {});
+  }
+  await run(async () => {
+    game = await api("POST", "/games", request);
+    heatmap = null;
+    document.getElementById("explanation").textContent = "";
+  });
+}
+
+async function clickTile(row, col) {
+  if (!game || game.result !== "in_progress" || game.board[row][col] !== "?") return;
+  await run(async () => {
+    if (memoMode) {
+      game = await api("POST", `/games/${game.id}/memo`, { row, col, value: memoValue });
+    } else {
+      game = await api("POST", `/games/${game.id}/flip`, { row, col });
+      heatmap = null;
+      document.getElementById("explanation").textContent = "";
+    }
+  });
+}
+
+async function showHeatmap() {
+  if (!game || game.result !== "in_progress") return;
+  await run(async () => {
+    const analysis = await api("GET", `/games/${game.id}/analysis`);
+    heatmap = analysis.safety;
+    game.hints_used += 1;
+    document.getElementById("explanation").textContent = analysis.explanation.join("\n");
+  });
+}
+
+// Runs one request and redraws, errors go to the status line
+async function run(action) {
+  try {
+    await action();
+    render();
+  } catch (err) {
+    document.getElementById("status").textContent = err.message;
+  }
+}
+
+// green for safe through yellow to red for a sure Voltorb
+function heatColor(voltorbChance) {
+  const red = Math.round(255 * Math.min(1, 2 * voltorbChance));
+  const green = Math.round(255 * Math.min(1, 2 * (1 - voltorbChance)));
+  return `rgb(${red}, ${green}, 60)`;
+}
+
+function clueCell(clue) {
+  const cell = document.createElement("td");
+  cell.className = "clue";
+  cell.innerHTML = `S:${clue.sum}<br>V:${clue.voltorbs}`;
+  return cell;
+}
+
+function tileCell(row, col) {
+  const cell = document.createElement("td");
+  const value = game.board[row][col];
+  cell.className = "tile";
+
+  if (value !== "?") {
+    cell.classList.add("revealed");
+    if (value === "V") cell.classList.add("voltorb");
+    cell.textContent = value === "V" ? "💥" : value;
+    return cell;
+  }
+
+  const safe = heatmap && heatmap[row][col];
+  if (safe !== null && safe !== undefined) {
+    cell.style.background = heatColor(1 - safe);
+    const label = document.createElement("span");
+    label.className = "heat";
+    label.textContent = `${Math.round((1 - safe) * 100)}%`;
+    cell.appendChild(label);
+  }
+
+  const memos = document.createElement("div");
+  memos.className = "memos";
+  for (const mark of ["V", "1", "2", "3"]) {
+    const slot = document.createElement("span");
+    slot.textContent = game.memos[row][col].includes(mark) ? mark : "";
+    memos.appendChild(slot);
+  }
+  cell.appendChild(memos);
+  cell.addEventListener("click", () => clickTile(row, col));
+  return cell;
+}
+
+function render() {
+  const table = document.getElementById("board");
+  table.innerHTML = "";
+  if (!game) return;
+
+  for (let row = 0; row < game.size; row++) {
+    const tr = document.createElement("tr");
+    for (let col = 0; col < game.size; col++) {
+      tr.appendChild(tileCell(row, col));
+    }
+    tr.appendChild(clueCell(game.row_clues[row]));
+    table.appendChild(tr);
+  }
+  const footer = document.createElement("tr");
+  for (const clue of game.col_clues) {
+    footer.appendChild(clueCell(clue));
+  }
+  table.appendChild(footer);
+
+  const result = { in_progress: "Keep going!", won: "You won! Run it back double or nothing", lost: "big boooooooom" }[game.result];
+  document.getElementById("status").textContent = `Score: ${game.score}  Hints used: ${game.hints_used}  ${result}`;
+  document.getElementById("memo-mode").classList.toggle("active", memoMode);
+  for (const button of document.querySelectorAll(".memo-value")) {
+    button.classList.toggle("active", memoMode && button.dataset.value === memoValue);
+  }
+}
+
+document.getElementById("new-game").addEventListener("click", newGame);
+document.getElementById("heatmap").addEventListener("click", showHeatmap);
+document.getElementById("memo-mode").addEventListener("click", () => {
+  memoMode = !memoMode;
+  render();
+});
+for (const button of document.querySelectorAll(".memo-value")) {
+  button.addEventListener("click", () => {
+    memoMode = true;
+    memoValue = button.dataset.value;
+    render();
+  });
+}
+
+newGame();
@@ -0,0 +1,58 @@
+/*
+ * C interface to the Voltorb Flip solver, implemented in src/ffi.rs.
+ * Build the library with `cargo build --release --no-default-features --features ffi`
+ * and link against target/release/libvoltorb_flip.so (or .dylib / .dll).
+ */
+#ifndef VOLTORB_FLIP_H
+#define VOLTORB_FLIP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Tile codes for the revealed grid */
+#define VF_HIDDEN 0
+#define VF_ONE 1
+#define VF_TWO 2
+#define VF_THREE 3
+#define VF_VOLTORB 66
+
+/* Largest board vf_position_new accepts, bigger ones could overflow the 8-bit clue sums */
+#define VF_MAX_SIZE 85
+
+/* Sum of the values and number of Voltorbs in one row or column */
+typedef struct VfClue {
+    uint8_t sum;
+    uint8_t voltorbs;
+} VfClue;
+
+typedef struct VfPosition VfPosition;
+typedef struct VfResult VfResult;
+
+/*
+ * row_clues and col_clues hold `size` clues each, tiles holds size * size tile codes row by row.
+ * Returns NULL on a null pointer, size 0 or over VF_MAX_SIZE, a clue that can't fit its line or an unknown
+ * tile code. Free with vf_position_free.
+ */
+VfPosition *vf_position_new(size_t size, const VfClue *row_clues, const VfClue *col_clues, const uint8_t *tiles);
+void vf_position_free(VfPosition *position);
+
+/* Returns NULL if the clues contradict the flipped tiles or the solver fails. Free with vf_result_free. */
+VfResult *vf_solve(const VfPosition *position);
+size_t vf_result_size(const VfResult *result);
+
+/* Chance the tile is safe, -1 for flipped tiles or positions off the board */
+float vf_result_probability(const VfResult *result, size_t row, size_t col);
+
+/* Fills in the recommended tile, returns 0 on success and -1 when nothing is left to flip */
+int32_t vf_result_best_move(const VfResult *result, size_t *row, size_t *col, float *probability);
+void vf_result_free(VfResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* VOLTORB_FLIP_H */
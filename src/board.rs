@@ -1,4 +1,5 @@
-use rand::{seq::SliceRandom, rng, Rng};
+use std::fmt;
+use rand::{seq::SliceRandom, Rng};
 
 
 #[derive(Clone)]
@@ -23,6 +24,23 @@ pub struct SumData {
     pub voltorb_count : u8
 }
 
+// Clues are stored as u8, a line of threes has to fit
+pub const MAX_BOARD_DIM: usize = u8::MAX as usize / 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationError {
+    EmptyBoard,
+    BoardTooLarge {
+        board_dim: usize,
+    },
+    TooManyTiles {
+        num_twos: usize,
+        num_threes: usize,
+        num_voltorbs: usize,
+        board_dim: usize,
+    },
+}
+
 #[derive(Clone)]
 pub struct PremadeBoard {
     pub solution: Board,
@@ -53,12 +71,68 @@ impl TileValue {
     }
 }
 
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerationError::EmptyBoard => write!(f, "board dimension must be at least 1"),
+            GenerationError::BoardTooLarge { board_dim } => write!(
+                f,
+                "a {}x{} board is too large, line sums only fit boards up to {}x{}",
+                board_dim, board_dim, MAX_BOARD_DIM, MAX_BOARD_DIM
+            ),
+            GenerationError::TooManyTiles { num_twos, num_threes, num_voltorbs, board_dim } => write!(
+                f,
+                "cannot place {} twos, {} threes and {} voltorbs ({} tiles) on a {}x{} board with {} tiles",
+                num_twos, num_threes, num_voltorbs,
+                num_twos + num_threes + num_voltorbs,
+                board_dim, board_dim, board_dim * board_dim
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
 impl PremadeBoard{
+    pub fn new(solution: Board, initial: Board) -> PremadeBoard {
+        let board_dim = solution.get_board_dim();
+        PremadeBoard { solution, initial, board_dim }
+    }
+
     pub fn get_board_dim(&self) -> usize {
         self.board_dim
     }
 }
 
+// (2s, 3s, voltorbs) a 5x5 board can have at each level in HGSS, every level has five layouts
+pub const LEVEL_LAYOUTS: [[(usize, usize, usize); 5]; 8] = [
+    [(3, 1, 6), (0, 3, 6), (5, 0, 6), (2, 2, 6), (4, 1, 6)],
+    [(1, 3, 7), (6, 0, 7), (3, 2, 7), (0, 4, 7), (5, 1, 7)],
+    [(2, 3, 8), (7, 0, 8), (4, 2, 8), (1, 4, 8), (6, 1, 8)],
+    [(3, 3, 8), (0, 5, 8), (8, 0, 10), (5, 2, 10), (2, 4, 10)],
+    [(7, 1, 10), (4, 3, 10), (1, 5, 10), (9, 0, 10), (6, 2, 10)],
+    [(3, 4, 10), (0, 6, 10), (8, 1, 10), (5, 3, 10), (2, 5, 10)],
+    [(7, 2, 10), (4, 4, 10), (1, 6, 13), (9, 1, 13), (6, 3, 10)],
+    [(0, 7, 10), (8, 2, 10), (5, 4, 10), (2, 6, 10), (7, 3, 10)],
+];
+pub const LEVEL_BOARD_DIM: usize = 5;
+
+// Picks one of the level's layouts, levels go from 1 to 8
+pub fn level_layout<R: Rng>(board_rng: &mut R, level: usize) -> Option<(usize, usize, usize)> {
+    let layouts = LEVEL_LAYOUTS.get(level.checked_sub(1)?)?;
+    Some(layouts[board_rng.random_range(0..layouts.len())])
+}
+
+// Random number of 2s, 3s or voltorbs for a board with max_tiles tiles.
+// Boards smaller than 3x3 are too small for the usual 1..max_tiles/4 range so they may get none.
+fn random_count<R: Rng>(board_rng: &mut R, max_tiles: usize) -> usize {
+    if max_tiles / 4 > 1 {
+        board_rng.random_range(1..max_tiles / 4)
+    } else {
+        board_rng.random_range(0..=max_tiles / 4)
+    }
+}
+
 impl Board {
 
     pub fn new(board_dim: usize, default_value: TileValue) -> Board {
@@ -84,28 +158,57 @@ impl Board {
         self.tiles[i][j] = val;
     }
 
+    #[cfg(feature = "thread-rng")]
     pub fn create_solution(
         &mut self,
         num_twos: Option<usize>,
         num_threes: Option<usize>,
-        num_voltorbs: Option<usize>) {
-        let mut rng_thrd = rand::rng();
+        num_voltorbs: Option<usize>) -> Result<(), GenerationError> {
+        self.create_solution_with_rng(&mut rand::rng(), num_twos, num_threes, num_voltorbs)
+    }
+
+    // same as create_solution but draws from the given rng so boards can be reproduced from a seed
+    pub fn create_solution_with_rng<R: Rng>(
+        &mut self,
+        board_rng: &mut R,
+        num_twos: Option<usize>,
+        num_threes: Option<usize>,
+        num_voltorbs: Option<usize>) -> Result<(), GenerationError> {
+        if self.board_dim == 0 {
+            return Err(GenerationError::EmptyBoard);
+        }
+        if self.board_dim > MAX_BOARD_DIM {
+            return Err(GenerationError::BoardTooLarge { board_dim: self.board_dim });
+        }
         let max_tiles = self.board_dim * self.board_dim;
 
-        let mut num_twos = num_twos.unwrap_or(rng_thrd.random_range(1..max_tiles/4));
-        let mut num_threes = num_threes.unwrap_or(rng_thrd.random_range(1..max_tiles/4));
-        let mut num_voltorbs = num_voltorbs.unwrap_or(rng_thrd.random_range(1..max_tiles/4));
+        // explicit counts that don't fit are an error, never silently scaled down
+        let requested = num_twos.unwrap_or(0) + num_threes.unwrap_or(0) + num_voltorbs.unwrap_or(0);
+        if requested > max_tiles {
+            return Err(GenerationError::TooManyTiles {
+                num_twos: num_twos.unwrap_or(0),
+                num_threes: num_threes.unwrap_or(0),
+                num_voltorbs: num_voltorbs.unwrap_or(0),
+                board_dim: self.board_dim,
+            });
+        }
+
+        // random counts only use whatever room the explicit ones left over
+        let mut remaining = max_tiles - requested;
+        let mut pick_count = |count: Option<usize>| match count {
+            Some(count) => count,
+            None => {
+                let count = random_count(board_rng, max_tiles).min(remaining);
+                remaining -= count;
+                count
+            }
+        };
+        let mut num_twos = pick_count(num_twos);
+        let mut num_threes = pick_count(num_threes);
+        let num_voltorbs = pick_count(num_voltorbs);
 
         let total_non_ones = num_twos + num_threes + num_voltorbs;
 
-        // Ensure total non-one tiles fit within board dimensions
-        if total_non_ones > max_tiles {
-            let scale_factor = max_tiles as f64 / total_non_ones as f64;
-            num_twos = (num_twos as f64 * scale_factor).floor() as usize;
-            num_threes = (num_threes as f64 * scale_factor).floor() as usize;
-            num_voltorbs = (num_voltorbs as f64 * scale_factor).floor() as usize;
-        }
-        
         // get all board positions and randomize
         let mut positions: Vec<(usize, usize)> = Vec::new();
         for row in 0..self.board_dim {
@@ -113,7 +216,7 @@ impl Board {
                 positions.push((row, col));
             }
         }
-        positions.shuffle(&mut rng());
+        positions.shuffle(board_rng);
         positions.truncate(total_non_ones);
 
         self.tiles = vec![vec![TileValue::One; self.board_dim]; self.board_dim];
@@ -125,23 +228,23 @@ impl Board {
                 num_threes -= 1;
                 TileValue::Three
             } else {
-                num_voltorbs -= 1;
                 TileValue::Voltorb
             };
             self.tiles[row][col] = to_place;
         }
+        Ok(())
     }
 
     pub fn get_row_sums(&self) -> Vec<SumData> {
         let mut row_sums = vec![SumData { value_sum: 0, voltorb_count: 0 }; self.board_dim];
 
-        for row in 0..self.board_dim {
+        for (row, sum) in row_sums.iter_mut().enumerate() {
             for col in 0..self.board_dim {
                 match self.tiles[row][col] {
-                    TileValue::One => row_sums[row].value_sum += 1,
-                    TileValue::Two => row_sums[row].value_sum += 2,
-                    TileValue::Three => row_sums[row].value_sum += 3,
-                    TileValue::Voltorb => row_sums[row].voltorb_count += 1,
+                    TileValue::One => sum.value_sum += 1,
+                    TileValue::Two => sum.value_sum += 2,
+                    TileValue::Three => sum.value_sum += 3,
+                    TileValue::Voltorb => sum.voltorb_count += 1,
                     _ => {}
                 }
             }
@@ -152,13 +255,13 @@ impl Board {
     pub fn get_col_sums(&self) -> Vec<SumData> {
         let mut col_sums = vec![SumData { value_sum: 0, voltorb_count: 0 }; self.board_dim];
 
-        for col in 0..self.board_dim {
+        for (col, sum) in col_sums.iter_mut().enumerate() {
             for row in 0..self.board_dim {
                 match self.tiles[row][col] {
-                    TileValue::One => col_sums[col].value_sum += 1,
-                    TileValue::Two => col_sums[col].value_sum += 2,
-                    TileValue::Three => col_sums[col].value_sum += 3,
-                    TileValue::Voltorb => col_sums[col].voltorb_count += 1,
+                    TileValue::One => sum.value_sum += 1,
+                    TileValue::Two => sum.value_sum += 2,
+                    TileValue::Three => sum.value_sum += 3,
+                    TileValue::Voltorb => sum.voltorb_count += 1,
                     _ => {}
                 }
             }
@@ -282,3 +385,138 @@ impl Board {
         ]
     }
 }
+
+// the generation tests draw from the thread rng
+#[cfg(all(test, feature = "thread-rng"))]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::solver;
+
+    const NUM_CASES: usize = 500;
+
+    // Tiny xorshift generator so the properties don't need an extra dependency
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        // uniform-ish value in lo..=hi
+        fn range(&mut self, lo: usize, hi: usize) -> usize {
+            lo + (self.next() % (hi - lo + 1) as u64) as usize
+        }
+
+        fn tile(&mut self) -> TileValue {
+            [TileValue::Hidden, TileValue::One, TileValue::Two, TileValue::Three, TileValue::Voltorb][self.range(0, 4)]
+        }
+    }
+
+    fn count(board: &Board, value: TileValue) -> usize {
+        board.get_tiles().iter().flatten().filter(|&&tile| tile == value).count()
+    }
+
+    #[test]
+    fn board_and_solver_sums_agree() {
+        let mut gen = XorShift(0x5EED_1234);
+        for _ in 0..NUM_CASES {
+            let board_dim = gen.range(1, 8);
+            let tiles: Vec<Vec<TileValue>> = (0..board_dim)
+                .map(|_| (0..board_dim).map(|_| gen.tile()).collect())
+                .collect();
+            let board = Board::from_tiles(tiles.clone());
+
+            let row_sums = board.get_row_sums();
+            let col_sums = board.get_col_sums();
+            assert_eq!(row_sums, solver::get_row_sums(&tiles));
+            assert_eq!(col_sums, solver::get_col_sums(&tiles));
+
+            // every tile is counted once by its row and once by its column
+            let total = |sums: &[SumData]| sums.iter()
+                .fold((0, 0), |acc, s| (acc.0 + s.value_sum as usize, acc.1 + s.voltorb_count as usize));
+            assert_eq!(total(&row_sums), total(&col_sums));
+            assert_eq!(total(&row_sums).1, count(&board, TileValue::Voltorb));
+        }
+    }
+
+    #[test]
+    fn create_solution_places_requested_counts() {
+        let mut gen = XorShift(0xB0A2_D5EE);
+        for _ in 0..NUM_CASES {
+            let board_dim = gen.range(3, 8);
+            let max_tiles = board_dim * board_dim;
+            let num_twos = gen.range(0, max_tiles);
+            let num_threes = gen.range(0, max_tiles - num_twos);
+            let num_voltorbs = gen.range(0, max_tiles - num_twos - num_threes);
+
+            let mut board = Board::new(board_dim, TileValue::Hidden);
+            board.create_solution(Some(num_twos), Some(num_threes), Some(num_voltorbs)).unwrap();
+
+            assert_eq!(count(&board, TileValue::Two), num_twos);
+            assert_eq!(count(&board, TileValue::Three), num_threes);
+            assert_eq!(count(&board, TileValue::Voltorb), num_voltorbs);
+            assert_eq!(count(&board, TileValue::One), max_tiles - num_twos - num_threes - num_voltorbs);
+            assert_eq!(count(&board, TileValue::Hidden), 0);
+        }
+    }
+
+    #[test]
+    fn create_solution_never_drops_tiles() {
+        let mut gen = XorShift(0x0DDB_0A2D);
+        for _ in 0..NUM_CASES {
+            let board_dim = gen.range(1, 8);
+            let max_tiles = board_dim * board_dim;
+            let num_twos = gen.range(0, max_tiles);
+            let num_threes = gen.range(0, max_tiles);
+            let num_voltorbs = gen.range(max_tiles + 1 - num_twos.min(num_threes), 2 * max_tiles);
+
+            // asking for more tiles than fit must be an error rather than place fewer
+            let mut board = Board::new(board_dim, TileValue::Hidden);
+            let outcome = board.create_solution(Some(num_twos), Some(num_threes), Some(num_voltorbs));
+            assert_eq!(outcome, Err(GenerationError::TooManyTiles { num_twos, num_threes, num_voltorbs, board_dim }));
+        }
+    }
+
+    #[test]
+    fn create_solution_handles_every_board_size() {
+        let mut gen = XorShift(0x711E_5123);
+        for board_dim in 1..=8 {
+            let max_tiles = board_dim * board_dim;
+            for _ in 0..50 {
+                // mix explicit and random counts, the explicit ones always fit
+                let num_twos = if gen.range(0, 1) == 0 { Some(gen.range(0, max_tiles)) } else { None };
+                let num_voltorbs = if gen.range(0, 1) == 0 { Some(gen.range(0, max_tiles - num_twos.unwrap_or(0))) } else { None };
+
+                let mut board = Board::new(board_dim, TileValue::Hidden);
+                board.create_solution(num_twos, None, num_voltorbs).unwrap();
+
+                assert_eq!(count(&board, TileValue::Hidden), 0);
+                if let Some(num_twos) = num_twos {
+                    assert_eq!(count(&board, TileValue::Two), num_twos);
+                }
+                if let Some(num_voltorbs) = num_voltorbs {
+                    assert_eq!(count(&board, TileValue::Voltorb), num_voltorbs);
+                }
+            }
+        }
+
+        let mut empty = Board::new(0, TileValue::Hidden);
+        assert_eq!(empty.create_solution(None, None, None), Err(GenerationError::EmptyBoard));
+    }
+
+    #[test]
+    fn boards_whose_sums_overflow_are_rejected() {
+        // a full line of threes is the largest sum a board can have
+        let mut largest = Board::new(MAX_BOARD_DIM, TileValue::Hidden);
+        largest.create_solution(Some(0), Some(MAX_BOARD_DIM * MAX_BOARD_DIM), Some(0)).unwrap();
+        assert!(largest.get_row_sums().iter().all(|sum| sum.value_sum as usize == 3 * MAX_BOARD_DIM));
+
+        let too_large = MAX_BOARD_DIM + 1;
+        let outcome = Game::try_new(too_large, None, Some(0), Some(too_large * too_large), Some(0));
+        assert_eq!(outcome.err(), Some(GenerationError::BoardTooLarge { board_dim: too_large }));
+    }
+}
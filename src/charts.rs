@@ -0,0 +1,193 @@
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::path::Path;
+
+const WIDTH: f64 = 1000.0;
+const HEIGHT: f64 = 500.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 30.0;
+const MARGIN_TOP: f64 = 50.0;
+const MARGIN_BOTTOM: f64 = 60.0;
+const NUM_TICKS: usize = 5;
+
+pub struct Series {
+    pub label: String,
+    pub color: &'static str,
+    pub points: Vec<(f64, f64)>,
+}
+
+// Data range that gets mapped onto the plot area
+struct Frame {
+    x_max: f64,
+    y_max: f64,
+}
+
+impl Series {
+    pub fn new(label: &str, color: &'static str, points: Vec<(f64, f64)>) -> Series {
+        Series { label: label.to_string(), color, points }
+    }
+}
+
+impl Frame {
+    fn plot_width() -> f64 {
+        WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+    }
+
+    fn plot_height() -> f64 {
+        HEIGHT - MARGIN_TOP - MARGIN_BOTTOM
+    }
+
+    fn x(&self, value: f64) -> f64 {
+        MARGIN_LEFT + value / self.x_max * Frame::plot_width()
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        HEIGHT - MARGIN_BOTTOM - value / self.y_max * Frame::plot_height()
+    }
+}
+
+// Line chart with both axes starting at 0, like the matplotlib plots in data/plots
+pub fn line_chart(title: &str, x_label: &str, y_label: &str, series: &[Series]) -> String {
+    let x_max = series.iter().flat_map(|s| s.points.iter().map(|p| p.0)).fold(0.0, f64::max);
+    let y_max = series.iter().flat_map(|s| s.points.iter().map(|p| p.1)).fold(0.0, f64::max);
+    let frame = Frame { x_max: nice_max(x_max), y_max: nice_max(y_max * 1.1) };
+
+    let mut svg = open_svg(title);
+    draw_axes(&mut svg, &frame, x_label, y_label, true);
+
+    for s in series {
+        let points: Vec<String> = s.points.iter()
+            .map(|&(x, y)| format!("{:.2},{:.2}", frame.x(x), frame.y(y)))
+            .collect();
+        writeln!(svg, r#"<polyline fill="none" stroke="{}" stroke-width="2" stroke-opacity="0.8" points="{}"/>"#,
+            s.color, points.join(" ")).unwrap();
+    }
+
+    draw_legend(&mut svg, series);
+    close_svg(svg)
+}
+
+// Grouped bar chart, point i of every series is drawn in category i
+pub fn bar_chart(title: &str, x_label: &str, y_label: &str, categories: &[String], series: &[Series]) -> String {
+    let y_max = series.iter().flat_map(|s| s.points.iter().map(|p| p.1)).fold(0.0, f64::max);
+    let frame = Frame { x_max: categories.len().max(1) as f64, y_max: nice_max(y_max * 1.1) };
+
+    let mut svg = open_svg(title);
+    draw_axes(&mut svg, &frame, x_label, y_label, false);
+
+    let group_width = Frame::plot_width() / categories.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / series.len().max(1) as f64;
+
+    for (i, category) in categories.iter().enumerate() {
+        let group_start = MARGIN_LEFT + i as f64 * group_width + group_width * 0.1;
+
+        for (k, s) in series.iter().enumerate() {
+            let value = s.points.get(i).map(|p| p.1).unwrap_or(0.0);
+            let x = group_start + k as f64 * bar_width;
+            let y = frame.y(value);
+            writeln!(svg, r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" fill-opacity="0.7"/>"#,
+                x, y, bar_width, HEIGHT - MARGIN_BOTTOM - y, s.color).unwrap();
+            writeln!(svg, r#"<text x="{:.2}" y="{:.2}" font-size="10" text-anchor="middle" fill="{}">{}</text>"#,
+                x + bar_width / 2.0, y - 4.0, s.color, format_value(value)).unwrap();
+        }
+
+        writeln!(svg, r#"<text x="{:.2}" y="{:.2}" font-size="12" text-anchor="middle">{}</text>"#,
+            MARGIN_LEFT + (i as f64 + 0.5) * group_width, HEIGHT - MARGIN_BOTTOM + 18.0, escape(category)).unwrap();
+    }
+
+    draw_legend(&mut svg, series);
+    close_svg(svg)
+}
+
+pub fn save_svg(path: &Path, svg: &str) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create plot directory");
+    }
+    fs::write(path, svg).expect("Failed to write SVG");
+    println!("Chart saved to {}", path.display());
+}
+
+fn open_svg(title: &str) -> String {
+    let mut svg = String::new();
+    writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif">"#,
+        WIDTH, HEIGHT, WIDTH, HEIGHT).unwrap();
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    writeln!(svg, r#"<text x="{}" y="30" font-size="18" text-anchor="middle">{}</text>"#,
+        WIDTH / 2.0, escape(title)).unwrap();
+    svg
+}
+
+fn close_svg(mut svg: String) -> String {
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn draw_axes(svg: &mut String, frame: &Frame, x_label: &str, y_label: &str, x_ticks: bool) {
+    let bottom = HEIGHT - MARGIN_BOTTOM;
+    let right = WIDTH - MARGIN_RIGHT;
+
+    for i in 0..=NUM_TICKS {
+        let value = frame.y_max * i as f64 / NUM_TICKS as f64;
+        let y = frame.y(value);
+        writeln!(svg, r##"<line x1="{}" y1="{:.2}" x2="{}" y2="{:.2}" stroke="#bbbbbb" stroke-dasharray="4 4"/>"##,
+            MARGIN_LEFT, y, right, y).unwrap();
+        writeln!(svg, r#"<text x="{}" y="{:.2}" font-size="12" text-anchor="end">{}</text>"#,
+            MARGIN_LEFT - 6.0, y + 4.0, format_value(value)).unwrap();
+
+        if x_ticks {
+            let value = frame.x_max * i as f64 / NUM_TICKS as f64;
+            writeln!(svg, r#"<text x="{:.2}" y="{}" font-size="12" text-anchor="middle">{}</text>"#,
+                frame.x(value), bottom + 18.0, format_value(value)).unwrap();
+        }
+    }
+
+    writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#, MARGIN_LEFT, bottom, right, bottom).unwrap();
+    writeln!(svg, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#, MARGIN_LEFT, MARGIN_TOP, MARGIN_LEFT, bottom).unwrap();
+
+    writeln!(svg, r#"<text x="{}" y="{}" font-size="14" text-anchor="middle">{}</text>"#,
+        MARGIN_LEFT + Frame::plot_width() / 2.0, HEIGHT - 15.0, escape(x_label)).unwrap();
+    writeln!(svg, r#"<text x="20" y="{}" font-size="14" text-anchor="middle" transform="rotate(-90 20 {})">{}</text>"#,
+        MARGIN_TOP + Frame::plot_height() / 2.0, MARGIN_TOP + Frame::plot_height() / 2.0, escape(y_label)).unwrap();
+}
+
+fn draw_legend(svg: &mut String, series: &[Series]) {
+    for (i, s) in series.iter().enumerate() {
+        let y = MARGIN_TOP + 15.0 + i as f64 * 20.0;
+        let x = WIDTH - MARGIN_RIGHT - 300.0;
+        writeln!(svg, r#"<rect x="{}" y="{}" width="14" height="14" fill="{}" fill-opacity="0.8"/>"#, x, y - 11.0, s.color).unwrap();
+        writeln!(svg, r#"<text x="{}" y="{}" font-size="12">{}</text>"#, x + 20.0, y, escape(&s.label)).unwrap();
+    }
+}
+
+// Rounds the axis maximum up to 1, 2 or 5 times a power of ten so the ticks land on round numbers
+fn nice_max(value: f64) -> f64 {
+    if value <= 0.0 || !value.is_finite() {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    let scaled = value / magnitude;
+    let nice = if scaled <= 1.0 {
+        1.0
+    } else if scaled <= 2.0 {
+        2.0
+    } else if scaled <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+fn format_value(value: f64) -> String {
+    if value == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else if value.abs() >= 1.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,608 @@
+// The commands behind the voltorb_flip binary, main.rs only picks which one to run
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::thread::{self, JoinHandle};
+use crate::board::{level_layout, Board, PremadeBoard, TileValue, LEVEL_BOARD_DIM};
+#[cfg(feature = "simulation")]
+use crate::board::MAX_BOARD_DIM;
+use crate::difficulty::generate_with_difficulty;
+use crate::game::{Game, GameState};
+use crate::profile::{self, Profile};
+use crate::solver::{explain_optimized_solver, position_safety, safety_grid};
+use crate::{protocol, puzzles, server, tui};
+#[cfg(feature = "simulation")]
+use crate::simulation::{run_simulation, run_stress_corpus, run_tournament};
+#[cfg(feature = "simulation")]
+use crate::solver::{exhaustive, optimized_solver};
+#[cfg(feature = "simulation")]
+use crate::sinks::{OutputConfig, OutputFormat};
+#[cfg(feature = "simulation")]
+use crate::stress::{generate_stress_boards, save_corpus, StressConfig, CORPUS_PATH, SOLVE_BUDGET_MS};
+
+// boards rated before 'd # #' gives up, most take milliseconds but an unlucky one can take seconds
+const DIFFICULTY_ATTEMPTS: usize = 50;
+// biggest board the advice thread runs on, a blank 5x5 takes milliseconds but a blank 6x6 takes most of a minute
+pub const ADVICE_MAX_DIM: usize = 5;
+
+type Advice = JoinHandle<Option<Vec<Vec<Option<f32>>>>>;
+
+// `voltorb_flip --protocol json` speaks one JSON command/response per line for bots and scripts
+pub fn protocol(name: Option<&str>) -> ExitCode {
+    if name != Some("json") {
+        eprintln!("Unknown protocol! The only supported one is '--protocol json'.");
+        return ExitCode::FAILURE;
+    }
+    if let Err(err) = protocol::run_protocol(io::stdin().lock(), io::stdout().lock()) {
+        eprintln!("Protocol error: {}", err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip tui [size]` plays full-screen instead of through the prompt
+pub fn tui(args: &[String]) -> ExitCode {
+    let board_dim = match args.first().map(|dim| dim.parse::<usize>()) {
+        None => 5,
+        Some(Ok(dim)) if dim > 0 => dim,
+        Some(_) => {
+            println!("Invalid board size! Use 'tui #' where # is the board size.");
+            return ExitCode::FAILURE;
+        }
+    };
+    let game = match Game::try_new(board_dim, None, None, None, None) {
+        Ok(game) => game,
+        Err(err) => {
+            println!("Couldn't make the board: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = tui::run_tui(game) {
+        println!("Couldn't start the full-screen mode: {}", err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip serve [port]` runs the REST API on localhost
+pub fn serve(args: &[String]) -> ExitCode {
+    let port = match args.first().map(|port| port.parse::<u16>()) {
+        None => 8080,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            println!("Invalid port! Use 'serve #' where # is the port number.");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = server::serve(&format!("127.0.0.1:{}", port)) {
+        println!("Couldn't start the server: {}", err);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip stats` prints the profile kept in VOLTORB_FLIP_HOME (~/.voltorb_flip by default)
+pub fn stats() -> ExitCode {
+    let dir = profile::profile_dir();
+    match Profile::load(&dir) {
+        Ok(player) => {
+            println!("Profile: {}", dir.join(profile::PROFILE_FILE).display());
+            player.display_stats();
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            println!("Couldn't read your profile in {}: {}", dir.display(), err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// `voltorb_flip daily [YYYY-MM-DD]` plays the day's shared board, today in UTC by default
+pub fn daily(args: &[String]) -> ExitCode {
+    let days = match args.first() {
+        None => puzzles::today(),
+        Some(date) => match puzzles::parse_date(date) {
+            Some(days) => days,
+            None => {
+                println!("Invalid date! Use 'daily YYYY-MM-DD', e.g. 'daily 2026-10-19'.");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    play_puzzle(&puzzles::daily_puzzle(days));
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip pack <name> [#]` plays a board from packs/<name>, by default the first one not won yet
+pub fn pack(args: &[String]) -> ExitCode {
+    let Some(name) = args.first() else {
+        println!("Usage: pack <name> [#]");
+        return ExitCode::FAILURE;
+    };
+    let pack = match puzzles::pack_dir(name).and_then(|dir| puzzles::load_pack(&dir)) {
+        Ok(pack) => pack,
+        Err(err) => {
+            println!("Couldn't load pack '{}': {}", name, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let results = puzzles::load_results(Path::new(puzzles::RESULTS_PATH)).unwrap_or_default();
+    let player = puzzles::player_name();
+    let won = |puzzle: &puzzles::Puzzle| results.iter()
+        .any(|result| result.puzzle == puzzle.id && result.player == player && result.result == "won");
+
+    println!("Pack '{}':", name);
+    for (index, puzzle) in pack.iter().enumerate() {
+        println!("  {:>2}. difficulty {:>4.1}{}", index + 1, puzzle.difficulty.score, if won(puzzle) { "  (won)" } else { "" });
+    }
+
+    let index = match args.get(1).map(|number| number.parse::<usize>()) {
+        None => pack.iter().position(|puzzle| !won(puzzle)).unwrap_or(0),
+        Some(Ok(number)) if (1..=pack.len()).contains(&number) => number - 1,
+        Some(_) => {
+            println!("Invalid puzzle! Pick a number between 1 and {}.", pack.len());
+            return ExitCode::FAILURE;
+        }
+    };
+    play_puzzle(&pack[index]);
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip new-pack <name> [seed]` writes packs/<name> with the starter pack's difficulty targets,
+// `new-pack starter` redraws the shipped starter pack
+pub fn new_pack(args: &[String]) -> ExitCode {
+    let (Some(name), Ok(seed)) = (
+        args.first(),
+        args.get(1).map_or(Ok(puzzles::STARTER_SEED), |seed| seed.parse::<u64>()),
+    ) else {
+        println!("Usage: new-pack <name> [seed]");
+        return ExitCode::FAILURE;
+    };
+    let dir = match puzzles::pack_dir(name) {
+        Ok(dir) => dir,
+        Err(err) => {
+            println!("Couldn't make pack '{}': {}", name, err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Drawing {} boards into {}...", puzzles::STARTER_TARGETS.len(), dir.display());
+    match puzzles::generate_pack(&dir, &puzzles::STARTER_TARGETS, seed) {
+        Ok(()) => {
+            println!("Pack '{}' is ready, play it with `pack {}`.", name, name);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            println!("Couldn't make pack '{}': {}", name, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// `voltorb_flip stress bench [--out <dir>] [--format json|jsonl|csv]` times the solver on the corpus,
+// fails if any board goes over the budget so scripts can catch regressions
+#[cfg(feature = "simulation")]
+pub fn stress_bench(args: &[String]) -> ExitCode {
+    let output = match output_options(args) {
+        Ok((output, rest)) if rest.is_empty() => output,
+        Ok(_) => {
+            println!("Usage: stress bench [--out <dir>] [--format json|jsonl|csv]");
+            return ExitCode::FAILURE;
+        }
+        Err(err) => {
+            println!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(slowest) = run_stress_corpus(&output) else {
+        return ExitCode::FAILURE;
+    };
+    println!("Slowest board took {:.1}ms, the budget is {:.0}ms", slowest, SOLVE_BUDGET_MS);
+    if slowest > SOLVE_BUDGET_MS {
+        println!("The solver got slower on the stress corpus!");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip stress [restarts]` searches for worst-case boards and rewrites the stress corpus
+#[cfg(feature = "simulation")]
+pub fn stress(args: &[String]) -> ExitCode {
+    let mut config = StressConfig::default();
+    match args.first().map(|restarts| restarts.parse::<usize>()) {
+        None => {}
+        Some(Ok(restarts)) if restarts > 0 => config.restarts = restarts,
+        Some(_) => {
+            println!("Invalid number of boards! Use 'stress #' where # is how many searches to run.");
+            return ExitCode::FAILURE;
+        }
+    }
+    let corpus = generate_stress_boards(&config);
+    for entry in &corpus {
+        println!("{:?}: {} solutions, {} values pruned", entry.goal, entry.solution_count, entry.values_pruned);
+    }
+    match save_corpus(Path::new(CORPUS_PATH), &corpus) {
+        Ok(()) => {
+            println!("Saved {} boards to {}", corpus.len(), CORPUS_PATH);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            println!("Couldn't save {}: {}", CORPUS_PATH, err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// `voltorb_flip simulate [--out <dir>] [--format json|jsonl|csv]` runs the benchmark batch and rewrites its results and charts
+#[cfg(feature = "simulation")]
+pub fn simulate(args: &[String]) -> ExitCode {
+    match output_options(args) {
+        Ok((output, rest)) if rest.is_empty() => {
+            run_simulation(&output);
+            return ExitCode::SUCCESS;
+        }
+        Ok(_) => println!("Usage: simulate [--out <dir>] [--format json|jsonl|csv]"),
+        Err(err) => println!("{}", err),
+    }
+    ExitCode::FAILURE
+}
+
+// `voltorb_flip tournament <boards> <size> <seed> [--out <dir>]` plays both solvers on the same seeded boards and compares them
+#[cfg(feature = "simulation")]
+pub fn tournament(args: &[String]) -> ExitCode {
+    let (output, rest) = match output_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            println!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let numbers: Vec<Option<u64>> = rest.iter().map(|arg| arg.parse().ok()).collect();
+    let [Some(boards), Some(size), Some(seed)] = numbers[..] else {
+        println!("Usage: tournament <boards> <size> <seed>, e.g. 'tournament 100 3 0'");
+        return ExitCode::FAILURE;
+    };
+    if boards < 2 || size == 0 || size > MAX_BOARD_DIM as u64 {
+        println!("A tournament needs at least 2 boards of size 1 to {}.", MAX_BOARD_DIM);
+        return ExitCode::FAILURE;
+    }
+    run_tournament(boards as usize, size as usize, seed, &[("ex", exhaustive), ("opt", optimized_solver)], &output);
+    ExitCode::SUCCESS
+}
+
+// `voltorb_flip` on its own asks for a board (premade, random, levelled or by difficulty) and plays it at the prompt
+pub fn prompt() -> ExitCode {
+    println!("Welcome to Dan's Casino!");
+
+    println!("Enter a number (0, 1, 2, ...) to pick a predefined board, 'r #' to generate a random board of size # by #");
+    println!("'d # #' for a 5x5 board with a difficulty score between the two numbers (0 is trivial, 20+ is brutal)");
+    println!("or 'l' for a board at your current level:");
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("Failed to read input");
+    let input = input.trim();
+    
+    let premade_boards = Board::premade_boards();
+    let mut custom_board = None;
+    let mut board_dim_input = 3; // default
+    let mut level = None;
+
+    if input.starts_with('r') {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        if parts.len() == 2 {
+            match parts[1].parse::<usize>() {
+                Ok(dim) if dim > 0 => {
+                    board_dim_input = dim;
+                }
+                _ => {
+                    println!("Invalid board size! Using default dimension {}.", board_dim_input);
+                }
+            }
+        } else {
+            println!("Invalid format! Use 'r #' where # is the board size.");
+            return ExitCode::FAILURE;
+        }
+    } else if input.eq_ignore_ascii_case("l") {
+        let player = Profile::load(&profile::profile_dir()).unwrap_or_default();
+        println!("Level {} board", player.level);
+        level = Some(player.level);
+    } else if input.starts_with('d') {
+        let bounds: Vec<f64> = input.split_whitespace().skip(1).filter_map(|bound| bound.parse().ok()).collect();
+        let [min_score, max_score] = bounds[..] else {
+            println!("Invalid format! Use 'd # #' with the lowest and highest difficulty score.");
+            return ExitCode::FAILURE;
+        };
+        board_dim_input = 5;
+        // one board per call so there's something to show while the slow ones get rated
+        print!("Looking for a board");
+        let mut found = None;
+        for _ in 0..DIFFICULTY_ATTEMPTS {
+            print!(".");
+            io::stdout().flush().expect("Failed to flush stdout");
+            found = generate_with_difficulty(&mut rand::rng(), board_dim_input, None, min_score, max_score, 1).ok().flatten();
+            if found.is_some() {
+                break;
+            }
+        }
+        println!();
+        match found {
+            Some((board, difficulty)) => {
+                println!("Difficulty {:.1}: {} forced guesses, {:.1}% chance to clear it without a mistake.",
+                    difficulty.score, difficulty.forced_guesses, difficulty.luck * 100.0);
+                let initial = Board::new(board_dim_input, TileValue::Hidden);
+                custom_board = Some(PremadeBoard::new(board, initial));
+            }
+            None => println!("Couldn't find a board in that range after {} tries. Using a random board with size {}.",
+                DIFFICULTY_ATTEMPTS, board_dim_input),
+        }
+    } else {
+        match input.parse::<usize>() {
+            Ok(index) if index < premade_boards.len() => {
+                custom_board = Some(premade_boards[index].clone());
+                board_dim_input = custom_board.as_ref().unwrap().get_board_dim(); // Set board dimension to match premade board
+            }
+            _ => {
+                println!("Invalid selection. Using a random board with default size {}.", board_dim_input);
+            }
+        }
+    }
+
+    // println!("{}", board_dim_input);
+
+    let generated = match level {
+        Some(level) => {
+            let (num_twos, num_threes, num_voltorbs) = level_layout(&mut rand::rng(), level).expect("Profile::load clamps the level to 1..=8");
+            Game::try_new(LEVEL_BOARD_DIM, None, Some(num_twos), Some(num_threes), Some(num_voltorbs))
+        }
+        None => Game::try_new(board_dim_input, custom_board, None, None, None),
+    };
+    let mut game = match generated {
+        Ok(game) => game,
+        Err(err) => {
+            println!("Couldn't make the board: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let played = play(&mut game);
+    record_game(&game, level, played);
+    ExitCode::SUCCESS
+}
+
+// Pulls `--out <dir>` and `--format json|jsonl|csv` out of the arguments, anything else is handed back.
+// Results go to data/ as JSON unless told otherwise.
+#[cfg(feature = "simulation")]
+fn output_options(args: &[String]) -> Result<(OutputConfig, Vec<String>), String> {
+    let mut output = OutputConfig::default();
+    let mut rest = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                let dir = args.next().ok_or("Missing directory after --out!")?;
+                output.dir = dir.into();
+            }
+            "--format" => {
+                let format = args.next().ok_or("Missing format after --format!")?;
+                output.format = OutputFormat::from_extension(format)
+                    .ok_or_else(|| format!("Unknown format '{}'! Use json, jsonl or csv.", format))?;
+            }
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok((output, rest))
+}
+
+// Prompt loop for one game, returns how many tiles were flipped and how many of those the solver would have picked too
+fn play(game: &mut Game) -> (usize, usize, usize) {
+    let board_dim = game.curr_board.get_board_dim();
+    let mut flips = 0;
+    let mut followed = 0;
+    let mut unknown = 0;
+    // the solver works out its advice while the player is still thinking, a flip never waits for it
+    let mut advice = spawn_advice(game);
+
+    println!("\nCurrent Board:");
+
+    game.display_board();
+
+    // each 'h' on the same board gives away a bit more
+    let mut hint_level = 0;
+    let mut heatmap = false;
+
+    loop {
+        println!("\nEnter row and column to flip (e.g., '1 2'), 'h' for a hint, 'v' to toggle the heatmap or type 'q' to quit:");
+
+        let mut input = String::new();
+        let bytes_read = io::stdin().read_line(&mut input).expect("Failed to read input");
+        let input = input.trim();
+
+        if bytes_read == 0 || input.eq_ignore_ascii_case("q") {
+            println!("99% of gamblers quit before they strike it big 💎🚀🔥");
+            break;
+        }
+
+        if input.eq_ignore_ascii_case("h") {
+            hint_level = (hint_level + 1).min(3);
+            give_hint(game, hint_level);
+            continue;
+        }
+
+        if input.eq_ignore_ascii_case("v") {
+            heatmap = !heatmap;
+            println!("\nHeatmap {}", if heatmap { "on" } else { "off" });
+            show_board(game, heatmap);
+            continue;
+        }
+
+        let mut parts = input.split_whitespace();
+        let row: usize = match parts.next().and_then(|r| r.parse().ok()) {
+            Some(num) if num < board_dim => num,
+            _ => {
+                println!("Invalid row! Enter a number between 0 and {}.", board_dim - 1);
+                continue;
+            }
+        };
+
+        let col: usize = match parts.next().and_then(|c| c.parse().ok()) {
+            Some(num) if num < board_dim => num,
+            _ => {
+                println!("Invalid column! Enter a number between 0 and {}.", board_dim - 1);
+                continue;
+            }
+        };
+
+        if game.curr_board.get_val(row, col) == TileValue::Hidden {
+            flips += 1;
+            match profile::finished_advice(&mut advice) {
+                Some(safety) if profile::followed_advice(&safety, row, col) => followed += 1,
+                Some(_) => {}
+                None => unknown += 1,
+            }
+        }
+        let result = game.click(row, col);
+        hint_level = 0;
+
+        println!("\nCurrent Board:");
+        show_board(game, heatmap);
+
+        match result {
+            GameState::Won => {
+                println!("\nRun it back double or nothing 🤑");
+                game.display_score();
+                break;
+            }
+            GameState::Lost => {
+                println!("\nbig boooooooom");
+                game.display_score();
+                break;
+            }
+            GameState::InProgress => {
+                println!("Keep going!");
+                advice = spawn_advice(game);
+            }
+        }
+    }
+    (flips, followed, unknown)
+}
+
+// Safety of every hidden tile in the current position, None if the solver finds a contradiction.
+// No thread at all past ADVICE_MAX_DIM, the flips there just count as unknown.
+fn spawn_advice(game: &Game) -> Option<Advice> {
+    if game.curr_board.get_board_dim() > ADVICE_MAX_DIM {
+        return None;
+    }
+    let tiles = game.curr_board.get_tiles().clone();
+    let (rows, cols) = (game.row_sums.clone(), game.col_sums.clone());
+    Some(thread::spawn(move || position_safety(&tiles, &rows, &cols).ok()))
+}
+
+// Adds the game to the player's profile, games abandoned before the first flip don't count
+fn record_game(game: &Game, level: Option<usize>, (flips, followed, unknown): (usize, usize, usize)) {
+    if flips == 0 {
+        return;
+    }
+    let dir = profile::profile_dir();
+    let mut player = match Profile::load(&dir) {
+        Ok(player) => player,
+        Err(err) => {
+            println!("Couldn't read your profile in {}: {}", dir.display(), err);
+            return;
+        }
+    };
+    player.record(game, level, flips, followed, unknown);
+    if let Err(err) = player.save(&dir) {
+        println!("Couldn't save your profile to {}: {}", dir.display(), err);
+        return;
+    }
+    println!("{} games played, {} won. Followed the solver on {} of {} flips this game.",
+        player.games_played, player.wins, followed, flips);
+}
+
+// Plays a daily or pack board, then records the attempt and shows how everyone else did on it
+fn play_puzzle(puzzle: &puzzles::Puzzle) {
+    println!("Puzzle {}: difficulty {:.1} with {} forced guesses",
+        puzzle.id, puzzle.difficulty.score, puzzle.difficulty.forced_guesses);
+
+    let mut game = puzzle.game();
+    let played = play(&mut game);
+    record_game(&game, None, played);
+    let flips = played.0;
+    // backing out before the first flip isn't an attempt
+    if flips == 0 {
+        return;
+    }
+
+    let path = Path::new(puzzles::RESULTS_PATH);
+    if let Err(err) = puzzles::record_result(path, &puzzles::game_result(puzzle, &game, flips)) {
+        println!("Couldn't save the result to {}: {}", path.display(), err);
+        return;
+    }
+    let results = puzzles::load_results(path).unwrap_or_default();
+    println!("\nResults on {}:", puzzle.id);
+    for (rank, result) in puzzles::leaderboard(&results, &puzzle.id).iter().take(10).enumerate() {
+        println!("{:>2}. {:<12} {:<11} {:>5} coins  {:>2} flips  {} hints  ({})",
+            rank + 1, result.player, result.result, result.score, result.flips, result.hints_used, result.date);
+    }
+}
+
+// The heatmap gives away as much as a level 3 hint so every render counts as one
+fn show_board(game: &mut Game, heatmap: bool) {
+    if !heatmap || game.get_state() != GameState::InProgress {
+        game.display_board();
+        return;
+    }
+    match safety_grid(game) {
+        Ok(grid) => {
+            game.use_hint();
+            game.display_heatmap(&grid);
+        }
+        Err(contradiction) => {
+            game.display_board();
+            println!("{}", contradiction);
+        }
+    }
+}
+
+// Level 1 points at a line, level 2 names a guaranteed-safe tile, level 3 shows every tile's odds
+fn give_hint(game: &mut Game, level: u8) {
+    game.use_hint();
+
+    let explanation = match explain_optimized_solver(game) {
+        Ok(explanation) => explanation,
+        Err(contradiction) => {
+            println!("{}", contradiction);
+            return;
+        }
+    };
+    let ((r, c), probability) = explanation.best_move;
+    if (r, c) == (usize::MAX, usize::MAX) {
+        println!("There are no safe tiles left to flip.");
+        return;
+    }
+
+    match level {
+        1 => {
+            // point at whichever line has fewer hidden tiles to choose from
+            let tiles = game.curr_board.get_tiles();
+            let row_hidden = tiles[r].iter().filter(|&&tile| tile == TileValue::Hidden).count();
+            let col_hidden = tiles.iter().filter(|row| row[c] == TileValue::Hidden).count();
+            if row_hidden <= col_hidden {
+                println!("Hint: take a closer look at row {}.", r);
+            } else {
+                println!("Hint: take a closer look at column {}.", c);
+            }
+        }
+        2 => {
+            if probability < 1.0 {
+                println!("Hint: no tile is guaranteed safe right now, you'll have to take a chance.");
+            } else {
+                println!("Hint: {}", explanation.lines().join("\n"));
+            }
+        }
+        _ => match safety_grid(game) {
+            Ok(grid) => {
+                println!("Hint: chance each hidden tile is safe");
+                game.display_safety_grid(&grid);
+            }
+            Err(contradiction) => println!("{}", contradiction),
+        },
+    }
+}
@@ -0,0 +1,137 @@
+// How hard a board is, measured by replaying the optimized solver on it.
+// The solver greedily flips the safest tile, which isn't always optimal, so forced guesses and luck describe
+// how a greedy player fares. A perfect player might need fewer guesses or take better odds on the same board.
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+use crate::board::{level_layout, Board, GenerationError, TileValue, LEVEL_BOARD_DIM};
+use crate::solver::{count_solutions, explain_position, get_col_sums, get_row_sums};
+
+// Counting stops here, 5x5 boards can fit millions of solutions and the score only takes the log anyway
+pub const SOLUTION_LIMIT: usize = 100_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    pub forced_guesses: usize,      // flips the solver couldn't prove safe
+    pub luck: f64,                  // chance of surviving every one of those guesses
+    pub initial_solutions: usize,   // boards that fit the clues before anything is flipped, capped at SOLUTION_LIMIT
+    pub score: f64,
+}
+
+impl Difficulty {
+    // Every bit of luck needed is a point, every forced guess is another and every 10x more starting boards is one more
+    fn new(forced_guesses: usize, luck: f64, initial_solutions: usize) -> Difficulty {
+        let score = -luck.log2() + forced_guesses as f64 + (initial_solutions.max(1) as f64).log10();
+        Difficulty { forced_guesses, luck, initial_solutions, score }
+    }
+}
+
+// Plays the board with the optimized solver until every 2 and 3 is flipped.
+// A guess that hits a Voltorb still only costs its probability, play carries on so every board gets a full rating.
+pub fn rate_board(board: &Board) -> Difficulty {
+    let solution = board.get_tiles();
+    let rows = get_row_sums(solution);
+    let cols = get_col_sums(solution);
+    let board_dim = board.get_board_dim();
+    let mut tiles = vec![vec![TileValue::Hidden; board_dim]; board_dim];
+
+    let initial_solutions = count_solutions(&tiles, &rows, &cols, SOLUTION_LIMIT);
+    let mut forced_guesses = 0;
+    let mut luck = 1.0;
+
+    while !multipliers_found(solution, &tiles) {
+        // the clues come from the solution itself so they can't contradict it
+        let Ok(explanation) = explain_position(&tiles, &rows, &cols) else {
+            break;
+        };
+        let ((r, c), probability) = explanation.best_move;
+        if r >= board_dim || c >= board_dim {
+            break;
+        }
+
+        if probability < 1.0 {
+            forced_guesses += 1;
+            luck *= probability as f64;
+        }
+        tiles[r][c] = solution[r][c];
+    }
+
+    Difficulty::new(forced_guesses, luck, initial_solutions)
+}
+
+fn multipliers_found(solution: &[Vec<TileValue>], tiles: &[Vec<TileValue>]) -> bool {
+    solution.iter().flatten().zip(tiles.iter().flatten())
+        .all(|(&sol, &tile)| !matches!(sol, TileValue::Two | TileValue::Three) || tile == sol)
+}
+
+// Draws boards until one scores between min_score and max_score, None if attempts run out first.
+// Level boards use the HGSS layouts so they are always 5x5, otherwise the tile counts are random.
+pub fn generate_with_difficulty<R: Rng>(
+    board_rng: &mut R,
+    board_dim: usize,
+    level: Option<usize>,
+    min_score: f64,
+    max_score: f64,
+    attempts: usize,
+) -> Result<Option<(Board, Difficulty)>, GenerationError> {
+    let board_dim = if level.is_some() { LEVEL_BOARD_DIM } else { board_dim };
+
+    for _ in 0..attempts {
+        let (num_twos, num_threes, num_voltorbs) = match level {
+            Some(level) => match level_layout(board_rng, level) {
+                Some((twos, threes, voltorbs)) => (Some(twos), Some(threes), Some(voltorbs)),
+                None => return Ok(None),
+            },
+            None => (None, None, None),
+        };
+        let mut board = Board::new(board_dim, TileValue::Hidden);
+        board.create_solution_with_rng(board_rng, num_twos, num_threes, num_voltorbs)?;
+
+        let difficulty = rate_board(&board);
+        if difficulty.score >= min_score && difficulty.score <= max_score {
+            return Ok(Some((board, difficulty)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn board(rows: &[&[u8]]) -> Board {
+        Board::from_tiles(rows.iter().map(|row| row.iter().map(|&val| TileValue::to_enum(val)).collect()).collect())
+    }
+
+    #[test]
+    fn fully_deducible_board_needs_no_luck() {
+        // every line without a Voltorb sums to its length, so every 1 and the lone 2 can be read straight off the clues
+        let rated = rate_board(&board(&[&[1, 1, 1], &[1, 2, 1], &[1, 1, 1]]));
+        assert_eq!(rated.forced_guesses, 0);
+        assert_eq!(rated.luck, 1.0);
+        assert_eq!(rated.initial_solutions, 1);
+        assert_eq!(rated.score, 0.0);
+    }
+
+    #[test]
+    fn unprunable_board_is_harder_than_an_easy_one() {
+        let easy = rate_board(&board(&[&[1, 1, 1], &[1, 2, 1], &[1, 1, 1]]));
+        let hard = rate_board(&Board::premade_boards()[4].solution);
+        assert!(hard.forced_guesses > 0);
+        assert!(hard.luck < 1.0);
+        assert!(hard.initial_solutions > 1);
+        assert!(hard.score > easy.score);
+    }
+
+    #[test]
+    fn generator_hits_the_requested_range() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let (board, difficulty) = generate_with_difficulty(&mut rng, 4, None, 2.0, 6.0, 200).unwrap().unwrap();
+        assert!((2.0..=6.0).contains(&difficulty.score));
+        assert_eq!(rate_board(&board), difficulty);
+
+        // nothing scores below zero
+        assert!(generate_with_difficulty(&mut rng, 3, None, -2.0, -1.0, 5).unwrap().is_none());
+        assert!(generate_with_difficulty(&mut rng, 4, Some(9), 0.0, 100.0, 5).unwrap().is_none());
+    }
+}
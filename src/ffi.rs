@@ -0,0 +1,209 @@
+// extern "C" API for the solver, declared in include/voltorb_flip.h
+// Tiles use the same codes as TileValue: 0 hidden, 1-3 for flipped values, 66 for a flipped Voltorb.
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use crate::board::{SumData, TileValue, MAX_BOARD_DIM};
+use crate::position::check_clues;
+use crate::solver::{explain_position, position_safety};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VfClue {
+    pub sum: u8,
+    pub voltorbs: u8,
+}
+
+// Opaque to C, only handled through pointers
+pub struct VfPosition {
+    tiles: Vec<Vec<TileValue>>,
+    rows: Vec<SumData>,
+    cols: Vec<SumData>,
+}
+
+pub struct VfResult {
+    size: usize,
+    best_move: (usize, usize),
+    probability: f32,
+    safety: Vec<Vec<Option<f32>>>,
+}
+
+/// # Safety
+/// `row_clues` and `col_clues` must point to `size` clues each and `tiles` to `size * size` bytes, row by row.
+/// Returns null if any pointer is null, `size` is 0 or over `VF_MAX_SIZE`, a clue can't fit its line
+/// or a tile code is unknown. Free with `vf_position_free`.
+#[no_mangle]
+pub unsafe extern "C" fn vf_position_new(
+    size: usize,
+    row_clues: *const VfClue,
+    col_clues: *const VfClue,
+    tiles: *const u8,
+) -> *mut VfPosition {
+    if size == 0 || size > MAX_BOARD_DIM || row_clues.is_null() || col_clues.is_null() || tiles.is_null() {
+        return ptr::null_mut();
+    }
+    let Some(tile_count) = size.checked_mul(size) else {
+        return ptr::null_mut();
+    };
+    let sums = |clues: &[VfClue]| clues.iter()
+        .map(|clue| SumData { value_sum: clue.sum, voltorb_count: clue.voltorbs })
+        .collect::<Vec<_>>();
+    let rows = sums(slice::from_raw_parts(row_clues, size));
+    let cols = sums(slice::from_raw_parts(col_clues, size));
+    let tiles = slice::from_raw_parts(tiles, tile_count);
+
+    // a panic can't unwind into C, so anything the checks miss comes back as null instead of aborting the host
+    panic::catch_unwind(move || {
+        if check_clues(&rows, &cols).is_err() {
+            return ptr::null_mut();
+        }
+        let mut grid = Vec::with_capacity(size);
+        for row in tiles.chunks(size) {
+            let parsed: Vec<TileValue> = row.iter().map(|&code| TileValue::to_enum(code)).collect();
+            if parsed.contains(&TileValue::ERR) {
+                return ptr::null_mut();
+            }
+            grid.push(parsed);
+        }
+        Box::into_raw(Box::new(VfPosition { tiles: grid, rows, cols }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+/// `position` must come from `vf_position_new` and not be freed yet, null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn vf_position_free(position: *mut VfPosition) {
+    if !position.is_null() {
+        drop(Box::from_raw(position));
+    }
+}
+
+/// # Safety
+/// `position` must come from `vf_position_new`. Returns null if the clues contradict the flipped tiles.
+/// Free the result with `vf_result_free`.
+#[no_mangle]
+pub unsafe extern "C" fn vf_solve(position: *const VfPosition) -> *mut VfResult {
+    let Some(position) = position.as_ref() else {
+        return ptr::null_mut();
+    };
+    // same as vf_position_new, a solver panic comes back as null
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        let (Ok(explanation), Ok(safety)) = (
+            explain_position(&position.tiles, &position.rows, &position.cols),
+            position_safety(&position.tiles, &position.rows, &position.cols),
+        ) else {
+            return ptr::null_mut();
+        };
+
+        let (best_move, probability) = explanation.best_move;
+        Box::into_raw(Box::new(VfResult { size: position.tiles.len(), best_move, probability, safety }))
+    }))
+    .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+/// `result` must come from `vf_solve`. Returns 0 for a null result.
+#[no_mangle]
+pub unsafe extern "C" fn vf_result_size(result: *const VfResult) -> usize {
+    result.as_ref().map_or(0, |result| result.size)
+}
+
+/// # Safety
+/// `result` must come from `vf_solve`.
+/// Chance the tile is safe, or -1 for flipped tiles and positions off the board.
+#[no_mangle]
+pub unsafe extern "C" fn vf_result_probability(result: *const VfResult, row: usize, col: usize) -> f32 {
+    result.as_ref()
+        .and_then(|result| result.safety.get(row)?.get(col).copied().flatten())
+        .unwrap_or(-1.0)
+}
+
+/// # Safety
+/// `result` must come from `vf_solve` and the out pointers must be writable.
+/// Returns 0 and fills in the solver's pick, or -1 when there is nothing left to flip.
+#[no_mangle]
+pub unsafe extern "C" fn vf_result_best_move(
+    result: *const VfResult,
+    row: *mut usize,
+    col: *mut usize,
+    probability: *mut f32,
+) -> i32 {
+    let Some(result) = result.as_ref() else {
+        return -1;
+    };
+    if result.best_move == (usize::MAX, usize::MAX) || row.is_null() || col.is_null() || probability.is_null() {
+        return -1;
+    }
+    *row = result.best_move.0;
+    *col = result.best_move.1;
+    *probability = result.probability;
+    0
+}
+
+/// # Safety
+/// `result` must come from `vf_solve` and not be freed yet, null is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn vf_result_free(result: *mut VfResult) {
+    if !result.is_null() {
+        drop(Box::from_raw(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = include_str!("../include/voltorb_flip.h");
+
+    #[test]
+    fn header_declares_every_export() {
+        let exports = [
+            "vf_position_new", "vf_position_free", "vf_solve",
+            "vf_result_size", "vf_result_probability", "vf_result_best_move", "vf_result_free",
+        ];
+        for name in exports {
+            assert!(HEADER.contains(&format!("{}(", name)), "{} missing from voltorb_flip.h", name);
+        }
+        assert!(HEADER.contains(&format!("#define VF_MAX_SIZE {}\n", MAX_BOARD_DIM)), "VF_MAX_SIZE is out of date");
+    }
+
+    #[test]
+    fn solves_a_position_through_the_c_api() {
+        let rows = [VfClue { sum: 3, voltorbs: 1 }, VfClue { sum: 5, voltorbs: 0 }, VfClue { sum: 3, voltorbs: 0 }];
+        let cols = [VfClue { sum: 2, voltorbs: 1 }, VfClue { sum: 5, voltorbs: 0 }, VfClue { sum: 4, voltorbs: 0 }];
+        let tiles = [0u8; 9];
+
+        unsafe {
+            let position = vf_position_new(3, rows.as_ptr(), cols.as_ptr(), tiles.as_ptr());
+            let result = vf_solve(position);
+            assert!(!result.is_null());
+            assert_eq!(vf_result_size(result), 3);
+            assert_eq!(vf_result_probability(result, 0, 0), 0.0);
+            assert_eq!(vf_result_probability(result, 5, 0), -1.0);
+
+            let (mut row, mut col, mut probability) = (0, 0, 0.0);
+            assert_eq!(vf_result_best_move(result, &mut row, &mut col, &mut probability), 0);
+            assert_eq!(probability, 1.0);
+            assert_eq!(vf_result_probability(result, row, col), 1.0);
+
+            vf_result_free(result);
+            vf_position_free(position);
+        }
+    }
+
+    #[test]
+    fn bad_input_gives_null() {
+        let clues = [VfClue { sum: 1, voltorbs: 0 }];
+        unsafe {
+            assert!(vf_position_new(1, clues.as_ptr(), clues.as_ptr(), [7u8].as_ptr()).is_null());
+            assert!(vf_position_new(0, clues.as_ptr(), clues.as_ptr(), [0u8].as_ptr()).is_null());
+            // a clue no single tile can have, and sizes past the u8 clue limit or whose tile count overflows
+            let impossible = [VfClue { sum: 255, voltorbs: 255 }];
+            assert!(vf_position_new(1, impossible.as_ptr(), impossible.as_ptr(), [0u8].as_ptr()).is_null());
+            assert!(vf_position_new(MAX_BOARD_DIM + 1, clues.as_ptr(), clues.as_ptr(), [0u8].as_ptr()).is_null());
+            assert!(vf_position_new(usize::MAX, clues.as_ptr(), clues.as_ptr(), [0u8].as_ptr()).is_null());
+            assert!(vf_solve(ptr::null()).is_null());
+        }
+    }
+}
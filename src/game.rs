@@ -1,4 +1,7 @@
-use crate::board::{Board, SumData, TileValue, PremadeBoard};
+use rand::{rngs::StdRng, SeedableRng};
+use crate::board::{Board, GenerationError, SumData, TileValue};
+#[cfg(feature = "thread-rng")]
+use crate::board::PremadeBoard;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameState {
@@ -13,26 +16,74 @@ pub struct Game {
     sol_board: Board,
     pub row_sums : Vec<SumData>,
     pub col_sums : Vec<SumData>,
-    state : GameState
+    state : GameState,
+    hints_used: u32,
+    memos: Vec<Vec<[bool; 4]>>,  // Voltorb, 1, 2, 3 notes the player left on each tile
 }
 
 impl Game {
+    // Panics if the board can't be generated (size 0, too large for u8 clues or tile counts that don't fit),
+    // anything built from user input should go through try_new
+    #[cfg(feature = "thread-rng")]
     pub fn new(
         board_dim: usize,
         custom_board: Option<PremadeBoard>,
         num_twos: Option<usize>,
         num_threes: Option<usize>,
         num_voltorbs: Option<usize>,) -> Game {
+        Game::try_new(board_dim, custom_board, num_twos, num_threes, num_voltorbs)
+            .unwrap_or_else(|err| panic!("Failed to generate board: {}", err))
+    }
+
+    // same as new but reports tile counts that can't be placed instead of panicking
+    #[cfg(feature = "thread-rng")]
+    pub fn try_new(
+        board_dim: usize,
+        custom_board: Option<PremadeBoard>,
+        num_twos: Option<usize>,
+        num_threes: Option<usize>,
+        num_voltorbs: Option<usize>,) -> Result<Game, GenerationError> {
         let (sol_board, curr_board) = match custom_board {
             Some(premade) => (premade.solution, premade.initial),
             None => {
                 let mut generated_board = Board::new(board_dim, TileValue::Hidden);
-                generated_board.create_solution(num_twos, num_threes, num_voltorbs);
+                generated_board.create_solution(num_twos, num_threes, num_voltorbs)?;
                 let curr_board = Board::new(board_dim, TileValue::Hidden);
                 (generated_board, curr_board)
             }
         };
 
+        Ok(Game::from_boards(sol_board, curr_board))
+    }
+
+    // builds a random board from a seed, the same seed always gives the same board.
+    // Panics like new does when the board can't be generated, try_new_seeded reports it instead
+    pub fn new_seeded(
+        board_dim: usize,
+        seed: u64,
+        num_twos: Option<usize>,
+        num_threes: Option<usize>,
+        num_voltorbs: Option<usize>,) -> Game {
+        Game::try_new_seeded(board_dim, seed, num_twos, num_threes, num_voltorbs)
+            .unwrap_or_else(|err| panic!("Failed to generate board: {}", err))
+    }
+
+    // same as new_seeded but reports boards that can't be generated instead of panicking
+    pub fn try_new_seeded(
+        board_dim: usize,
+        seed: u64,
+        num_twos: Option<usize>,
+        num_threes: Option<usize>,
+        num_voltorbs: Option<usize>,) -> Result<Game, GenerationError> {
+        let mut seeded_rng = StdRng::seed_from_u64(seed);
+        let mut sol_board = Board::new(board_dim, TileValue::Hidden);
+        sol_board.create_solution_with_rng(&mut seeded_rng, num_twos, num_threes, num_voltorbs)?;
+        let curr_board = Board::new(board_dim, TileValue::Hidden);
+        Ok(Game::from_boards(sol_board, curr_board))
+    }
+
+    fn from_boards(sol_board: Board, curr_board: Board) -> Game {
+        let board_dim = curr_board.get_board_dim();
         let row_sums = sol_board.get_row_sums();
         let col_sums = sol_board.get_col_sums();
 
@@ -45,6 +96,8 @@ impl Game {
             row_sums,
             col_sums,
             state: GameState::InProgress,
+            hints_used: 0,
+            memos: vec![vec![[false; 4]; board_dim]; board_dim],
         }
     }
 
@@ -98,7 +151,16 @@ impl Game {
     // }
 
     // formatting functions
-    pub fn display_board(&self, guess: ((usize, usize), f32)) {
+    pub fn display_board(&self) {
+        self.print_board(None);
+    }
+
+    // same board but every hidden tile shows its chance of being a Voltorb, coloured green (safe) to red
+    pub fn display_heatmap(&self, safety: &[Vec<Option<f32>>]) {
+        self.print_board(Some(safety));
+    }
+
+    fn print_board(&self, safety: Option<&[Vec<Option<f32>>]>) {
         let board_dim = self.curr_board.get_board_dim();
         let green_square = "🟩";
         let numbers = [" ", " 1️⃣   ", " 2️⃣   ", " 3️⃣   ", "💥", "💀"];
@@ -127,6 +189,11 @@ impl Game {
             print!("R{} |", row);
             for col in 0..board_dim {
                 let tile = self.curr_board.get_val(row, col);
+                let heat = safety.and_then(|grid| grid[row][col]);
+                if let (TileValue::Hidden, Some(safe)) = (tile, heat) {
+                    print!(" {} ", heat_label(1.0 - safe));
+                    continue;
+                }
                 let symbol = match tile {
                     TileValue::Hidden => green_square.to_string(),
                     TileValue::One => numbers[1].to_string(),
@@ -152,15 +219,88 @@ impl Game {
             print!(" C{}    ", col);
         }
         println!();
-        println!{"Solver says: \"You should pick ({} {}). This tile has a probability of {} to be safe.", guess.0.0, guess.0.1, guess.1}
+    }
+
+    // hidden tiles show their chance of being safe as a percentage
+    pub fn display_safety_grid(&self, grid: &[Vec<Option<f32>>]) {
+        let board_dim = self.curr_board.get_board_dim();
+
+        print!("     ");
+        for col in 0..board_dim {
+            print!(" C{}    ", col);
+        }
+        println!();
+
+        for (row, probabilities) in grid.iter().enumerate() {
+            print!("R{} |", row);
+            for probability in probabilities {
+                match probability {
+                    Some(p) => print!(" {:>4.0}% ", p * 100.0),
+                    None => print!("   --  "),
+                }
+            }
+            println!();
+        }
     }
 
     pub fn display_score(&self) {
         println!("Your score is: {}", self.score);
+        println!("Hints used: {}", self.hints_used);
+    }
+
+    // flips a memo mark on a tile, only hidden tiles take memos
+    pub fn toggle_memo(&mut self, i: usize, j: usize, val: TileValue) {
+        if self.curr_board.get_val(i, j) != TileValue::Hidden {
+            return;
+        }
+        if let Some(slot) = memo_slot(val) {
+            self.memos[i][j][slot] = !self.memos[i][j][slot];
+        }
+    }
+
+    pub fn get_memos(&self, i: usize, j: usize) -> Vec<TileValue> {
+        [TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three]
+            .into_iter()
+            .filter(|&val| memo_slot(val).is_some_and(|slot| self.memos[i][j][slot]))
+            .collect()
+    }
+
+    pub fn use_hint(&mut self) {
+        self.hints_used += 1;
+    }
+
+    pub fn get_hints_used(&self) -> u32 {
+        self.hints_used
+    }
+
+    pub fn get_score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn get_state(&self) -> GameState {
+        self.state
     }
 
     pub fn get_sol(&self) -> Board {
-        return self.sol_board.clone();
+        self.sol_board.clone()
     }
 
 }
+
+fn memo_slot(val: TileValue) -> Option<usize> {
+    match val {
+        TileValue::Voltorb => Some(0),
+        TileValue::One => Some(1),
+        TileValue::Two => Some(2),
+        TileValue::Three => Some(3),
+        _ => None,
+    }
+}
+
+// Voltorb probability as a percentage on a green -> yellow -> red background
+fn heat_label(voltorb_chance: f32) -> String {
+    let p = voltorb_chance.clamp(0.0, 1.0);
+    let red = (255.0 * (2.0 * p).min(1.0)) as u8;
+    let green = (255.0 * (2.0 * (1.0 - p)).min(1.0)) as u8;
+    format!("\x1b[48;2;{};{};60m\x1b[30m{:>3.0}%\x1b[0m", red, green, p * 100.0)
+}
@@ -0,0 +1,43 @@
+mod board;
+mod difficulty;
+mod game;
+mod position;
+mod solver;
+
+#[cfg(feature = "simulation")]
+mod charts;
+#[cfg(feature = "simulation")]
+pub mod simulation;
+#[cfg(feature = "simulation")]
+pub mod sinks;
+#[cfg(feature = "simulation")]
+pub mod stress;
+
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cli")]
+mod profile;
+#[cfg(feature = "cli")]
+mod protocol;
+#[cfg(feature = "cli")]
+mod puzzles;
+#[cfg(feature = "cli")]
+mod server;
+#[cfg(feature = "cli")]
+mod tui;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+// The pieces most callers need, e.g. `voltorb_flip::optimized_solver(&mut game)`
+pub use board::{Board, GenerationError, PremadeBoard, SumData, TileValue, MAX_BOARD_DIM};
+pub use difficulty::{generate_with_difficulty, rate_board, Difficulty};
+pub use game::{Game, GameState};
+pub use position::{parse_tile, tile_label, Analysis, AnalysisError, Clue, Position};
+pub use solver::{
+    count_solutions, exhaustive, explain_optimized_solver, explain_position, optimized_solver,
+    position_safety, pruning_progress, safety_grid, try_optimized_solver, Contradiction, Deduction,
+    Explanation, Rule,
+};
@@ -0,0 +1,167 @@
+use serde::{Serialize, Deserialize};
+use crate::board::{SumData, TileValue, MAX_BOARD_DIM};
+use crate::solver::{explain_position, position_safety, Contradiction};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clue {
+    pub sum: u8,
+    pub voltorbs: u8,
+}
+
+// A position described only by what a player can see, e.g. one copied from a real cartridge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub board: Vec<Vec<String>>,  // "?" hidden, "V" Voltorb, otherwise the tile's value
+    pub row_clues: Vec<Clue>,
+    pub col_clues: Vec<Clue>,
+}
+
+// The solver's view of a position
+#[derive(Serialize, Debug, Clone)]
+pub struct Analysis {
+    pub row: usize,
+    pub col: usize,
+    pub probability: f32,
+    pub explanation: Vec<String>,
+    pub safety: Vec<Vec<Option<f32>>>,  // chance each hidden tile is safe, null for flipped tiles
+}
+
+// Tiles plus row and column sums in the shape the solver takes
+pub type ParsedPosition = (Vec<Vec<TileValue>>, Vec<SumData>, Vec<SumData>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnalysisError {
+    BadPosition(String),
+    Contradiction(Contradiction),
+    NothingToFlip,
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AnalysisError::BadPosition(message) => write!(f, "{}", message),
+            AnalysisError::Contradiction(contradiction) => write!(f, "{}", contradiction),
+            AnalysisError::NothingToFlip => write!(f, "there are no hidden tiles to flip"),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+pub fn tile_label(tile: TileValue) -> String {
+    match tile {
+        TileValue::Hidden => String::from("?"),
+        TileValue::Voltorb => String::from("V"),
+        other => other.to_value().to_string(),
+    }
+}
+
+pub fn parse_tile(label: &str) -> Option<TileValue> {
+    match label {
+        "?" => Some(TileValue::Hidden),
+        "V" | "v" => Some(TileValue::Voltorb),
+        "1" => Some(TileValue::One),
+        "2" => Some(TileValue::Two),
+        "3" => Some(TileValue::Three),
+        _ => None,
+    }
+}
+
+impl Position {
+    pub fn parse(&self) -> Result<ParsedPosition, AnalysisError> {
+        let size = self.board.len();
+        if size == 0 || self.board.iter().any(|row| row.len() != size)
+            || self.row_clues.len() != size || self.col_clues.len() != size
+        {
+            return Err(AnalysisError::BadPosition(String::from(
+                "board must be square with one clue per row and column")));
+        }
+        let sums = |clues: &[Clue]| clues.iter()
+            .map(|clue| SumData { value_sum: clue.sum, voltorb_count: clue.voltorbs })
+            .collect::<Vec<_>>();
+        let (rows, cols) = (sums(&self.row_clues), sums(&self.col_clues));
+        check_clues(&rows, &cols)?;
+
+        let mut tiles = Vec::with_capacity(size);
+        for row in &self.board {
+            let mut parsed = Vec::with_capacity(size);
+            for label in row {
+                let Some(tile) = parse_tile(label) else {
+                    return Err(AnalysisError::BadPosition(format!(
+                        "unknown tile {:?}, use \"?\", \"V\", \"1\", \"2\" or \"3\"", label)));
+                };
+                parsed.push(tile);
+            }
+            tiles.push(parsed);
+        }
+        Ok((tiles, rows, cols))
+    }
+
+    pub fn analyze(&self) -> Result<Analysis, AnalysisError> {
+        let (tiles, rows, cols) = self.parse()?;
+        analyze_tiles(&tiles, &rows, &cols)
+    }
+}
+
+// The board has to fit u8 clue sums and every clue has to be one a real line could have: at most one Voltorb
+// per tile and every other tile worth 1 to 3. The solver's u8 arithmetic relies on this, so anything taking
+// clues from outside checks them first.
+pub fn check_clues(rows: &[SumData], cols: &[SumData]) -> Result<(), AnalysisError> {
+    let size = rows.len().max(cols.len());
+    if size > MAX_BOARD_DIM {
+        return Err(AnalysisError::BadPosition(format!(
+            "a {}x{} board is too large, the limit is {}x{}", size, size, MAX_BOARD_DIM, MAX_BOARD_DIM)));
+    }
+    for (kind, clues, line_len) in [("row", rows, cols.len()), ("column", cols, rows.len())] {
+        for (index, clue) in clues.iter().enumerate() {
+            let voltorbs = clue.voltorb_count as usize;
+            let fits = voltorbs <= line_len
+                && (line_len - voltorbs..=3 * (line_len - voltorbs)).contains(&(clue.value_sum as usize));
+            if !fits {
+                return Err(AnalysisError::BadPosition(format!(
+                    "{} {} clue (sum {}, {} Voltorbs) doesn't fit a line of {} tiles",
+                    kind, index, clue.value_sum, clue.voltorb_count, line_len)));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn analyze_tiles(tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData]) -> Result<Analysis, AnalysisError> {
+    check_clues(rows, cols)?;
+    let explanation = explain_position(tiles, rows, cols).map_err(AnalysisError::Contradiction)?;
+    let safety = position_safety(tiles, rows, cols).map_err(AnalysisError::Contradiction)?;
+    let ((row, col), probability) = explanation.best_move;
+    if (row, col) == (usize::MAX, usize::MAX) {
+        return Err(AnalysisError::NothingToFlip);
+    }
+
+    Ok(Analysis { row, col, probability, explanation: explanation.lines(), safety })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank(size: usize, clue: Clue) -> Position {
+        Position {
+            board: vec![vec![String::from("?"); size]; size],
+            row_clues: vec![clue; size],
+            col_clues: vec![clue; size],
+        }
+    }
+
+    #[test]
+    fn positions_that_would_overflow_the_clues_are_rejected() {
+        assert!(blank(3, Clue { sum: 3, voltorbs: 0 }).parse().is_ok());
+
+        let too_big = blank(MAX_BOARD_DIM + 1, Clue { sum: 100, voltorbs: 0 });
+        assert!(matches!(too_big.parse(), Err(AnalysisError::BadPosition(message)) if message.contains("too large")));
+
+        // rule 4 used to add these two as u8
+        for clue in [Clue { sum: 255, voltorbs: 255 }, Clue { sum: 10, voltorbs: 0 }, Clue { sum: 0, voltorbs: 1 }] {
+            let err = blank(3, clue).analyze().unwrap_err();
+            assert!(err.to_string().contains("doesn't fit a line of 3 tiles"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+// The player's lifetime record, kept in a JSON file so it survives between runs
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use serde::{Serialize, Deserialize};
+use crate::board::LEVEL_LAYOUTS;
+use crate::game::{Game, GameState};
+use crate::protocol::state_label;
+use crate::puzzles::{format_date, today};
+
+pub const PROFILE_DIR_VAR: &str = "VOLTORB_FLIP_HOME";
+pub const PROFILE_FILE: &str = "profile.json";
+const HISTORY_LEN: usize = 50;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameRecord {
+    pub date: String,
+    pub board_size: usize,
+    pub level: Option<usize>,  // only games started at the player's level have one
+    pub result: String,        // "won", "lost" or "in_progress" if the player quit
+    pub coins: u32,
+    pub flips: usize,
+    pub followed_advice: usize,
+    #[serde(default)]
+    pub unknown_advice: usize,  // flips made before the solver's advice was ready
+    pub hints_used: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    pub games_played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub coins: u64,
+    pub level: usize,          // where the next level game starts, moves like it does in HGSS
+    pub highest_level: usize,
+    pub total_flips: u64,
+    pub followed_advice: u64,  // flips onto a tile the solver rated as safe as any other
+    pub unknown_advice: u64,   // flips the solver had no advice for yet, left out of advice_rate
+    pub history: Vec<GameRecord>,  // most recent last, capped at HISTORY_LEN
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile {
+            games_played: 0,
+            wins: 0,
+            losses: 0,
+            coins: 0,
+            level: 1,
+            highest_level: 1,
+            total_flips: 0,
+            followed_advice: 0,
+            unknown_advice: 0,
+            history: Vec::new(),
+        }
+    }
+}
+
+// VOLTORB_FLIP_HOME if set, otherwise ~/.voltorb_flip, otherwise .voltorb_flip in the working directory
+pub fn profile_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os(PROFILE_DIR_VAR) {
+        return PathBuf::from(dir);
+    }
+    match std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        Some(home) => PathBuf::from(home).join(".voltorb_flip"),
+        None => PathBuf::from(".voltorb_flip"),
+    }
+}
+
+// True if no hidden tile had better odds than the one flipped
+pub fn followed_advice(safety: &[Vec<Option<f32>>], row: usize, col: usize) -> bool {
+    let best = safety.iter().flatten().flatten().fold(0.0f32, |best, &p| best.max(p));
+    safety.get(row).and_then(|line| line.get(col).copied().flatten())
+        .is_some_and(|p| p >= best - 1e-6)
+}
+
+// The advice if the solver thread is done, otherwise None straight away. The handle is taken either way since
+// the flip makes the advice stale, a thread still working finishes in the background and is ignored.
+pub fn finished_advice<T>(advice: &mut Option<JoinHandle<Option<T>>>) -> Option<T> {
+    let handle = advice.take()?;
+    if !handle.is_finished() {
+        return None;
+    }
+    handle.join().ok().flatten()
+}
+
+impl Profile {
+    // No file yet means a new player. Levels outside 1..=8 from a hand-edited or older file are clamped
+    // so the next level game can always be laid out.
+    pub fn load(dir: &Path) -> io::Result<Profile> {
+        let path = dir.join(PROFILE_FILE);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Profile::default()),
+            Err(err) => return Err(err),
+        };
+        let mut profile: Profile = serde_json::from_str(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        profile.level = profile.level.clamp(1, LEVEL_LAYOUTS.len());
+        profile.highest_level = profile.highest_level.clamp(profile.level, LEVEL_LAYOUTS.len());
+        Ok(profile)
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self).expect("Failed to serialize JSON");
+        fs::write(dir.join(PROFILE_FILE), json)
+    }
+
+    // Adds a finished or abandoned game. Coins only count on a win, same as the autoplay results.
+    pub fn record(&mut self, game: &Game, level: Option<usize>, flips: usize, followed_advice: usize, unknown_advice: usize) {
+        let state = game.get_state();
+        let coins = if state == GameState::Won { game.get_score() } else { 0 };
+
+        self.games_played += 1;
+        match state {
+            GameState::Won => self.wins += 1,
+            GameState::Lost => self.losses += 1,
+            GameState::InProgress => {}
+        }
+        self.coins += coins as u64;
+        self.total_flips += flips as u64;
+        self.followed_advice += followed_advice as u64;
+        self.unknown_advice += unknown_advice as u64;
+
+        // HGSS rules: a win moves up a level, anything else drops to the number of tiles flipped if that's lower
+        if let Some(level) = level {
+            self.level = if state == GameState::Won {
+                (level + 1).min(LEVEL_LAYOUTS.len())
+            } else {
+                flips.clamp(1, level)
+            };
+            self.highest_level = self.highest_level.max(self.level).max(level);
+        }
+
+        self.history.push(GameRecord {
+            date: format_date(today()),
+            board_size: game.curr_board.get_board_dim(),
+            level,
+            result: String::from(state_label(state)),
+            coins,
+            flips,
+            followed_advice,
+            unknown_advice,
+            hints_used: game.get_hints_used(),
+        });
+        if self.history.len() > HISTORY_LEN {
+            self.history.remove(0);
+        }
+    }
+
+    pub fn average_flips(&self) -> f64 {
+        if self.games_played == 0 {
+            return 0.0;
+        }
+        self.total_flips as f64 / self.games_played as f64
+    }
+
+    // Flips the solver had advice for
+    pub fn advised_flips(&self) -> u64 {
+        self.total_flips.saturating_sub(self.unknown_advice)
+    }
+
+    // Share of advised flips that matched the solver, 0 before the first one
+    pub fn advice_rate(&self) -> f64 {
+        if self.advised_flips() == 0 {
+            return 0.0;
+        }
+        self.followed_advice as f64 / self.advised_flips() as f64
+    }
+
+    pub fn display_stats(&self) {
+        // saturating so a hand-edited file with more wins than games still prints
+        let quits = self.games_played.saturating_sub(self.wins).saturating_sub(self.losses);
+        println!("Games played: {} ({} won, {} lost, {} quit)", self.games_played, self.wins, self.losses, quits);
+        if self.games_played > 0 {
+            println!("Win rate: {:.1}%", self.wins as f64 / self.games_played as f64 * 100.0);
+        }
+        println!("Coins: {}", self.coins);
+        println!("Level: {} (highest {})", self.level, self.highest_level);
+        println!("Average flips per game: {:.1}", self.average_flips());
+        println!("Followed the solver's advice: {:.1}% of {} flips ({} made before the advice was ready)",
+            self.advice_rate() * 100.0, self.advised_flips(), self.unknown_advice);
+
+        if !self.history.is_empty() {
+            println!("\nRecent games:");
+            for record in self.history.iter().rev().take(10) {
+                let level = record.level.map_or(String::from("-"), |level| level.to_string());
+                println!("  {}  {}x{}  level {:<2} {:<11} {:>5} coins  {:>2} flips  {} followed  {} hints",
+                    record.date, record.board_size, record.board_size, level, record.result,
+                    record.coins, record.flips, record.followed_advice, record.hints_used);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{Board, PremadeBoard, TileValue};
+
+    fn game(solution: &[&[u8]]) -> Game {
+        let solution = Board::from_tiles(solution.iter().map(|row| row.iter().map(|&val| TileValue::to_enum(val)).collect()).collect());
+        let initial = Board::new(solution.get_board_dim(), TileValue::Hidden);
+        Game::new(solution.get_board_dim(), Some(PremadeBoard::new(solution, initial)), None, None, None)
+    }
+
+    #[test]
+    fn records_outcomes_and_moves_the_level() {
+        let mut profile = Profile::default();
+
+        let mut won = game(&[&[1, 2], &[66, 3]]);
+        won.click(0, 1);
+        won.click(1, 1);
+        profile.record(&won, Some(1), 2, 2, 0);
+        profile.record(&won, Some(2), 2, 1, 0);
+        assert_eq!((profile.level, profile.highest_level), (3, 3));
+
+        let mut lost = game(&[&[1, 2], &[66, 3]]);
+        lost.click(0, 0);
+        lost.click(1, 0);
+        profile.record(&lost, Some(3), 2, 0, 1);
+        assert_eq!((profile.level, profile.highest_level), (2, 3));
+
+        // quitting a free game doesn't touch the level
+        profile.record(&game(&[&[1, 2], &[66, 3]]), None, 0, 0, 0);
+        assert_eq!(profile.level, 2);
+
+        assert_eq!((profile.games_played, profile.wins, profile.losses), (4, 2, 1));
+        assert_eq!(profile.coins, 12);
+        assert_eq!(profile.average_flips(), 1.5);
+        // the flip made before the advice was ready doesn't count against the player
+        assert_eq!((profile.advised_flips(), profile.unknown_advice), (5, 1));
+        assert_eq!(profile.advice_rate(), 0.6);
+        assert_eq!(profile.history.len(), 4);
+        assert_eq!(profile.history[2].result, "lost");
+        assert_eq!(profile.history[2].coins, 0);
+    }
+
+    #[test]
+    fn level_never_passes_eight_and_history_is_capped() {
+        let mut profile = Profile::default();
+        let mut won = game(&[&[2]]);
+        won.click(0, 0);
+        for _ in 0..HISTORY_LEN + 5 {
+            let level = profile.level;
+            profile.record(&won, Some(level), 1, 1, 0);
+        }
+        assert_eq!((profile.level, profile.highest_level), (8, 8));
+        assert_eq!(profile.history.len(), HISTORY_LEN);
+        assert_eq!(profile.games_played as usize, HISTORY_LEN + 5);
+    }
+
+    #[test]
+    fn saves_and_loads_from_the_directory() {
+        let dir = std::env::temp_dir().join(format!("voltorb_profile_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(Profile::load(&dir).unwrap(), Profile::default());
+
+        let mut profile = Profile::default();
+        profile.record(&game(&[&[1, 2], &[66, 3]]), None, 0, 0, 0);
+        profile.save(&dir).unwrap();
+        assert_eq!(Profile::load(&dir).unwrap(), profile);
+
+        // files from older versions missing newer fields still load
+        fs::write(dir.join(PROFILE_FILE), r#"{"games_played": 3, "wins": 1}"#).unwrap();
+        let old = Profile::load(&dir).unwrap();
+        assert_eq!((old.games_played, old.wins, old.level), (3, 1, 1));
+
+        // hand-edited levels are pulled back into range, more wins than games doesn't break the stats
+        fs::write(dir.join(PROFILE_FILE), r#"{"level": 0, "highest_level": 0, "games_played": 1, "wins": 4}"#).unwrap();
+        assert_eq!(Profile::load(&dir).unwrap().level, 1);
+        fs::write(dir.join(PROFILE_FILE), r#"{"level": 99, "highest_level": 3, "games_played": 1, "wins": 4}"#).unwrap();
+        let edited = Profile::load(&dir).unwrap();
+        assert_eq!((edited.level, edited.highest_level), (8, 8));
+        edited.display_stats();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn advice_counts_ties_with_the_best_tile() {
+        let safety = vec![vec![Some(0.5), None], vec![Some(0.75), Some(0.75)]];
+        assert!(followed_advice(&safety, 1, 0));
+        assert!(followed_advice(&safety, 1, 1));
+        assert!(!followed_advice(&safety, 0, 0));
+        assert!(!followed_advice(&safety, 0, 1));
+    }
+
+    #[test]
+    fn unfinished_advice_is_skipped_without_waiting() {
+        let mut advice = Some(std::thread::spawn(|| {
+            std::thread::sleep(std::time::Duration::from_secs(2));
+            Some(1)
+        }));
+        let start = std::time::Instant::now();
+        assert_eq!(finished_advice(&mut advice), None);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(advice.is_none());
+
+        let handle = std::thread::spawn(|| Some(2));
+        while !handle.is_finished() {
+            std::thread::yield_now();
+        }
+        assert_eq!(finished_advice(&mut Some(handle)), Some(2));
+    }
+}
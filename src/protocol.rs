@@ -0,0 +1,256 @@
+use std::io::{self, BufRead, Write};
+use serde::{Serialize, Deserialize};
+use crate::board::{Board, SumData, TileValue};
+use crate::game::{Game, GameState};
+use crate::position::{tile_label, Clue};
+use crate::server::MAX_GAME_SIZE;
+use crate::solver::explain_optimized_solver;
+
+// One JSON object per line on stdin, e.g. {"cmd": "flip", "row": 1, "col": 2}
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    New {
+        #[serde(default = "default_size")]
+        size: usize,
+        seed: Option<u64>,
+        premade: Option<usize>,
+    },
+    Flip { row: usize, col: usize },
+    State,
+    Hint,
+    Quit,
+}
+
+// One JSON object per line on stdout, the "type" field says which
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    State(GameView),
+    Hint {
+        row: usize,
+        col: usize,
+        probability: f32,
+        explanation: Vec<String>,
+        hints_used: u32,
+    },
+    Bye { score: u32 },
+    Error { message: String },
+}
+
+// What a player can see, the solution stays hidden
+#[derive(Serialize, Debug)]
+pub struct GameView {
+    pub size: usize,
+    pub board: Vec<Vec<String>>,  // "?" hidden, "V" Voltorb, otherwise the tile's value
+    pub memos: Vec<Vec<Vec<String>>>,  // the player's notes on each hidden tile, same labels
+    pub row_clues: Vec<Clue>,
+    pub col_clues: Vec<Clue>,
+    pub result: String,           // "in_progress", "won" or "lost"
+    pub score: u32,
+    pub hints_used: u32,
+}
+
+fn default_size() -> usize {
+    5
+}
+
+impl GameView {
+    pub fn of(game: &Game) -> GameView {
+        let board = game.curr_board.get_tiles().iter()
+            .map(|row| row.iter().map(|&tile| tile_label(tile)).collect())
+            .collect();
+        let size = game.curr_board.get_board_dim();
+        let memos = (0..size)
+            .map(|r| (0..size).map(|c| game.get_memos(r, c).into_iter().map(tile_label).collect()).collect())
+            .collect();
+        let clues = |sums: &[SumData]| sums.iter()
+            .map(|sum| Clue { sum: sum.value_sum, voltorbs: sum.voltorb_count })
+            .collect();
+
+        GameView {
+            size,
+            board,
+            memos,
+            row_clues: clues(&game.row_sums),
+            col_clues: clues(&game.col_sums),
+            result: state_label(game.get_state()).to_string(),
+            score: game.get_score(),
+            hints_used: game.get_hints_used(),
+        }
+    }
+}
+
+pub fn state_label(state: GameState) -> &'static str {
+    match state {
+        GameState::InProgress => "in_progress",
+        GameState::Won => "won",
+        GameState::Lost => "lost",
+    }
+}
+
+// Reads commands until "quit" or end of input, answering each one on its own line
+pub fn run_protocol<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    let mut game = None;
+
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Command>(&line) {
+            Ok(command) => handle(&mut game, command),
+            Err(err) => Response::Error { message: format!("bad command: {}", err) },
+        };
+        writeln!(output, "{}", serde_json::to_string(&response).expect("Failed to serialize JSON"))?;
+        output.flush()?;
+
+        if let Response::Bye { .. } = response {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub fn handle(game: &mut Option<Game>, command: Command) -> Response {
+    if let Command::New { size, seed, premade } = command {
+        return match new_game(size, seed, premade) {
+            Ok(new_game) => {
+                let view = GameView::of(&new_game);
+                *game = Some(new_game);
+                Response::State(view)
+            }
+            Err(message) => Response::Error { message },
+        };
+    }
+
+    // quitting always ends the session, even before the first game
+    if let Command::Quit = command {
+        return Response::Bye { score: game.as_ref().map_or(0, |game| game.get_score()) };
+    }
+
+    let Some(current) = game.as_mut() else {
+        return Response::Error { message: String::from("no game yet, send {\"cmd\": \"new\"} first") };
+    };
+
+    match command {
+        Command::New { .. } | Command::Quit => unreachable!(),
+        Command::Flip { row, col } => {
+            let size = current.curr_board.get_board_dim();
+            if row >= size || col >= size {
+                return Response::Error { message: format!("({},{}) is off the {}x{} board", row, col, size, size) };
+            }
+            if current.get_state() != GameState::InProgress {
+                return Response::Error { message: String::from("the game is over") };
+            }
+            if current.curr_board.get_val(row, col) != TileValue::Hidden {
+                return Response::Error { message: format!("({},{}) is already flipped", row, col) };
+            }
+            current.click(row, col);
+            Response::State(GameView::of(current))
+        }
+        Command::State => Response::State(GameView::of(current)),
+        Command::Hint => {
+            if current.get_state() != GameState::InProgress {
+                return Response::Error { message: String::from("the game is over") };
+            }
+            current.use_hint();
+            match explain_optimized_solver(current) {
+                Ok(explanation) => {
+                    let ((row, col), probability) = explanation.best_move;
+                    Response::Hint { row, col, probability, explanation: explanation.lines(), hints_used: current.get_hints_used() }
+                }
+                Err(contradiction) => Response::Error { message: contradiction.to_string() },
+            }
+        }
+    }
+}
+
+fn new_game(size: usize, seed: Option<u64>, premade: Option<usize>) -> Result<Game, String> {
+    if let Some(index) = premade {
+        let premade_boards = Board::premade_boards();
+        let Some(board) = premade_boards.get(index) else {
+            return Err(format!("there are only {} premade boards", premade_boards.len()));
+        };
+        return Game::try_new(board.get_board_dim(), Some(board.clone()), None, None, None).map_err(|err| err.to_string());
+    }
+    // same cap as the server, hints on a bigger board could keep the solver busy for good
+    if size == 0 || size > MAX_GAME_SIZE {
+        return Err(format!("size must be between 1 and {}", MAX_GAME_SIZE));
+    }
+    match seed {
+        Some(seed) => Game::try_new_seeded(size, seed, None, None, None),
+        None => Game::try_new(size, None, None, None, None),
+    }
+    .map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    // Feeds the lines through the protocol and parses every response
+    fn converse(lines: &[&str]) -> Vec<Value> {
+        let mut output = Vec::new();
+        run_protocol(lines.join("\n").as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn new_game_reports_clues_and_hidden_board() {
+        let responses = converse(&[r#"{"cmd": "new", "size": 4, "seed": 3}"#]);
+        assert_eq!(responses[0]["type"], "state");
+        assert_eq!(responses[0]["size"], 4);
+        assert_eq!(responses[0]["row_clues"].as_array().unwrap().len(), 4);
+        assert_eq!(responses[0]["board"][2][1], "?");
+        assert_eq!(responses[0]["result"], "in_progress");
+    }
+
+    #[test]
+    fn following_hints_reveals_safe_tiles_and_quits() {
+        let responses = converse(&[
+            r#"{"cmd": "new", "premade": 0}"#,
+            r#"{"cmd": "hint"}"#,
+            r#"{"cmd": "quit"}"#,
+            r#"{"cmd": "state"}"#,
+        ]);
+        assert_eq!(responses.len(), 3, "nothing is read after quit");
+        assert_eq!(responses[1]["type"], "hint");
+        assert_eq!(responses[1]["probability"], 1.0);
+        assert_eq!(responses[1]["hints_used"], 1);
+        assert_eq!(responses[2]["type"], "bye");
+
+        let (row, col) = (responses[1]["row"].as_u64().unwrap(), responses[1]["col"].as_u64().unwrap());
+        let flip = format!(r#"{{"cmd": "flip", "row": {}, "col": {}}}"#, row, col);
+        let responses = converse(&[r#"{"cmd": "new", "premade": 0}"#, &flip]);
+        assert_ne!(responses[1]["board"][row as usize][col as usize], "V");
+    }
+
+    #[test]
+    fn bad_commands_get_errors_not_crashes() {
+        let responses = converse(&[
+            r#"{"cmd": "flip", "row": 0, "col": 0}"#,
+            "not json",
+            r#"{"cmd": "new", "size": 3, "seed": 1}"#,
+            r#"{"cmd": "flip", "row": 7, "col": 0}"#,
+            r#"{"cmd": "new", "size": 100, "seed": 1}"#,
+            r#"{"cmd": "new", "size": 0}"#,
+            r#"{"cmd": "state"}"#,
+        ]);
+        let types: Vec<&Value> = responses.iter().map(|response| &response["type"]).collect();
+        assert_eq!(types, ["error", "error", "state", "error", "error", "error", "state"]);
+        assert_eq!(responses[4]["message"], "size must be between 1 and 8");
+    }
+
+    #[test]
+    fn quit_ends_the_session_without_a_game() {
+        let responses = converse(&[r#"{"cmd": "quit"}"#, r#"{"cmd": "new", "size": 3, "seed": 1}"#]);
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["type"], "bye");
+        assert_eq!(responses[0]["score"], 0);
+    }
+}
@@ -0,0 +1,416 @@
+// Daily challenge and curated puzzle packs, plus the results file players compare their scores in
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Serialize, Deserialize};
+use crate::board::{Board, PremadeBoard, TileValue, LEVEL_BOARD_DIM, LEVEL_LAYOUTS};
+use crate::difficulty::{generate_with_difficulty, rate_board, Difficulty};
+use crate::game::{Game, GameState};
+use crate::position::{parse_tile, tile_label};
+use crate::protocol::state_label;
+
+pub const PACKS_DIR: &str = "packs";
+pub const RESULTS_PATH: &str = "puzzle_results.jsonl";
+
+// (level, lowest score, highest score) for each board of packs/starter, from a gentle start to a nasty finish
+pub const STARTER_TARGETS: [(usize, f64, f64); 6] =
+    [(1, 0.0, 2.0), (2, 2.0, 4.0), (3, 4.0, 7.0), (5, 7.0, 10.0), (6, 10.0, 14.0), (8, 14.0, 20.0)];
+// what packs/starter was generated with
+pub const STARTER_SEED: u64 = 2026;
+const PACK_ATTEMPTS: usize = 500;
+
+// One board file in a pack, tiles use the same labels as Position ("V", "1", "2", "3")
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PuzzleFile {
+    pub solution: Vec<Vec<String>>,
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,  // filled in by rate_board when the curator left it out
+}
+
+#[derive(Clone)]
+pub struct Puzzle {
+    pub id: String,  // what results are keyed by, e.g. "daily-2026-10-19" or "starter/03"
+    pub board: Board,
+    pub difficulty: Difficulty,
+}
+
+// One finished (or abandoned) attempt, a line of RESULTS_PATH
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PuzzleResult {
+    pub puzzle: String,
+    pub player: String,
+    pub date: String,
+    pub result: String,  // "won", "lost" or "in_progress" if the player quit
+    pub score: u32,
+    pub flips: usize,
+    pub hints_used: u32,
+}
+
+#[derive(Debug)]
+pub enum PackError {
+    Io(PathBuf, io::Error),
+    BadBoard(PathBuf, String),
+    Empty(PathBuf),
+    NoBoard(usize, f64, f64),  // level, lowest and highest score of a target generate_pack couldn't hit
+    BadName(String),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackError::Io(path, err) => write!(f, "couldn't access {}: {}", path.display(), err),
+            PackError::BadBoard(path, message) => write!(f, "{}: {}", path.display(), message),
+            PackError::Empty(path) => write!(f, "{} has no .json board files", path.display()),
+            PackError::NoBoard(level, min_score, max_score) => write!(f,
+                "no level {} board scored between {} and {} in {} tries", level, min_score, max_score, PACK_ATTEMPTS),
+            PackError::BadName(name) => write!(f, "'{}' isn't a pack name, use a plain name without path separators or '..'", name),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl Puzzle {
+    // Fresh game on the puzzle's board with every tile hidden
+    pub fn game(&self) -> Game {
+        let initial = Board::new(self.board.get_board_dim(), TileValue::Hidden);
+        Game::new(self.board.get_board_dim(), Some(PremadeBoard::new(self.board.clone(), initial)), None, None, None)
+    }
+}
+
+impl PuzzleFile {
+    pub fn from_board(board: &Board, difficulty: Difficulty) -> PuzzleFile {
+        let solution = board.get_tiles().iter().map(|row| row.iter().map(|&tile| tile_label(tile)).collect()).collect();
+        PuzzleFile { solution, difficulty: Some(difficulty) }
+    }
+
+    fn board(&self) -> Result<Board, String> {
+        let size = self.solution.len();
+        if size == 0 || self.solution.iter().any(|row| row.len() != size) {
+            return Err(String::from("solution must be a non-empty square grid"));
+        }
+        let mut tiles = Vec::with_capacity(size);
+        for row in &self.solution {
+            let mut parsed = Vec::with_capacity(size);
+            for label in row {
+                match parse_tile(label) {
+                    Some(TileValue::Hidden) | None => return Err(format!(
+                        "unknown tile {:?}, a solution only holds \"V\", \"1\", \"2\" or \"3\"", label)),
+                    Some(tile) => parsed.push(tile),
+                }
+            }
+            tiles.push(parsed);
+        }
+        Ok(Board::from_tiles(tiles))
+    }
+}
+
+// Days since 1970-01-01 in UTC
+pub fn today() -> u64 {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock is before 1970");
+    elapsed.as_secs() / 86_400
+}
+
+// "YYYY-MM-DD" to days since 1970-01-01, None for malformed or impossible dates like 2026-02-30
+pub fn parse_date(date: &str) -> Option<u64> {
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts[..] else {
+        return None;
+    };
+    let (year, month, day) = (year.parse::<i64>().ok()?, month.parse::<u32>().ok()?, day.parse::<u32>().ok()?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    if days < 0 || civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(days as u64)
+}
+
+pub fn format_date(days: u64) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Howard Hinnant's calendar algorithms, proleptic Gregorian
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// SplitMix64, written out here because rand doesn't promise StdRng, random_range or shuffle give the same
+// values from one version to the next and a date's daily board must never change
+struct DailyRng(u64);
+
+impl DailyRng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // 0..n, the modulo bias is far too small to matter for the handful of values used here
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// Everyone gets the same board on the same day, the date seeds the level, the layout and where every tile goes
+pub fn daily_board(days: u64) -> Board {
+    let mut daily_rng = DailyRng(days);
+    let layouts = &LEVEL_LAYOUTS[daily_rng.below(LEVEL_LAYOUTS.len())];
+    let (num_twos, num_threes, num_voltorbs) = layouts[daily_rng.below(layouts.len())];
+
+    // Fisher-Yates over every tile, the first ones drawn get the 2s, then the 3s, then the Voltorbs
+    let mut positions: Vec<(usize, usize)> = (0..LEVEL_BOARD_DIM)
+        .flat_map(|row| (0..LEVEL_BOARD_DIM).map(move |col| (row, col)))
+        .collect();
+    for i in (1..positions.len()).rev() {
+        positions.swap(i, daily_rng.below(i + 1));
+    }
+
+    let mut tiles = vec![vec![TileValue::One; LEVEL_BOARD_DIM]; LEVEL_BOARD_DIM];
+    for (index, &(row, col)) in positions.iter().take(num_twos + num_threes + num_voltorbs).enumerate() {
+        tiles[row][col] = if index < num_twos {
+            TileValue::Two
+        } else if index < num_twos + num_threes {
+            TileValue::Three
+        } else {
+            TileValue::Voltorb
+        };
+    }
+    Board::from_tiles(tiles)
+}
+
+pub fn daily_puzzle(days: u64) -> Puzzle {
+    let board = daily_board(days);
+    let difficulty = rate_board(&board);
+    Puzzle { id: format!("daily-{}", format_date(days)), board, difficulty }
+}
+
+// Pack names become a directory under packs/, so anything that could point outside it is turned away
+pub fn pack_dir(name: &str) -> Result<PathBuf, PackError> {
+    let mut components = Path::new(name).components();
+    let plain = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+    if !plain || name.contains(['/', '\\']) {
+        return Err(PackError::BadName(name.to_string()));
+    }
+    Ok(Path::new(PACKS_DIR).join(name))
+}
+
+// Every .json file in the directory in file name order, so curators control the order with the names
+pub fn load_pack(dir: &Path) -> Result<Vec<Puzzle>, PackError> {
+    let pack_name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let entries = fs::read_dir(dir).map_err(|err| PackError::Io(dir.to_path_buf(), err))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        return Err(PackError::Empty(dir.to_path_buf()));
+    }
+
+    let mut puzzles = Vec::with_capacity(paths.len());
+    for path in paths {
+        let json = fs::read_to_string(&path).map_err(|err| PackError::Io(path.clone(), err))?;
+        let file: PuzzleFile = serde_json::from_str(&json).map_err(|err| PackError::BadBoard(path.clone(), err.to_string()))?;
+        let board = file.board().map_err(|message| PackError::BadBoard(path.clone(), message))?;
+        let difficulty = file.difficulty.unwrap_or_else(|| rate_board(&board));
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+        puzzles.push(Puzzle { id: format!("{}/{}", pack_name, stem), board, difficulty });
+    }
+    Ok(puzzles)
+}
+
+// Draws one rated board per target and writes them into dir as 01.json, 02.json, ... so load_pack keeps the
+// target order. Boards come from StdRng, which can change with rand, so rerunning a seed only reproduces the
+// shipped files on the rand version that made them; the files are what's shipped, not the seed
+pub fn generate_pack(dir: &Path, targets: &[(usize, f64, f64)], seed: u64) -> Result<(), PackError> {
+    fs::create_dir_all(dir).map_err(|err| PackError::Io(dir.to_path_buf(), err))?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    for (index, &(level, min_score, max_score)) in targets.iter().enumerate() {
+        let (board, difficulty) =
+            generate_with_difficulty(&mut rng, LEVEL_BOARD_DIM, Some(level), min_score, max_score, PACK_ATTEMPTS)
+                .map_err(|err| PackError::BadBoard(dir.to_path_buf(), err.to_string()))?
+                .ok_or(PackError::NoBoard(level, min_score, max_score))?;
+        let path = dir.join(format!("{:02}.json", index + 1));
+        let json = serde_json::to_string_pretty(&PuzzleFile::from_board(&board, difficulty)).expect("Failed to serialize JSON");
+        fs::write(&path, json).map_err(|err| PackError::Io(path.clone(), err))?;
+    }
+    Ok(())
+}
+
+pub fn player_name() -> String {
+    std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_else(|_| String::from("anonymous"))
+}
+
+pub fn game_result(puzzle: &Puzzle, game: &Game, flips: usize) -> PuzzleResult {
+    PuzzleResult {
+        puzzle: puzzle.id.clone(),
+        player: player_name(),
+        date: format_date(today()),
+        result: String::from(state_label(game.get_state())),
+        score: if game.get_state() == GameState::Won { game.get_score() } else { 0 },
+        flips,
+        hints_used: game.get_hints_used(),
+    }
+}
+
+// Appends one line, the file is shared so earlier results are never rewritten
+pub fn record_result(path: &Path, result: &PuzzleResult) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(result).expect("Failed to serialize JSON"))
+}
+
+// No file yet just means nobody has played, lines that don't parse are skipped
+pub fn load_results(path: &Path) -> io::Result<Vec<PuzzleResult>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    Ok(contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+// Results on one puzzle, best first: wins, then coins, then fewest hints and flips
+pub fn leaderboard<'a>(results: &'a [PuzzleResult], puzzle: &str) -> Vec<&'a PuzzleResult> {
+    let mut board: Vec<&PuzzleResult> = results.iter().filter(|result| result.puzzle == puzzle).collect();
+    board.sort_by_key(|result| (result.result != "won", std::cmp::Reverse(result.score), result.hints_used, result.flips));
+    board
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2000-03-01"), Some(11_017));
+        assert_eq!(parse_date("2024-02-29").map(format_date), Some(String::from("2024-02-29")));
+        for days in (0..40_000).step_by(97) {
+            assert_eq!(parse_date(&format_date(days)), Some(days));
+        }
+        for bad in ["2026-02-30", "2023-02-29", "2026-13-01", "1969-12-31", "2026-1", "tomorrow"] {
+            assert_eq!(parse_date(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn daily_board_depends_only_on_the_date() {
+        let day = parse_date("2026-10-19").unwrap();
+        let first = daily_board(day);
+
+        assert_eq!(first.get_tiles(), daily_board(day).get_tiles());
+        assert_ne!(first.get_tiles(), daily_board(day + 1).get_tiles());
+        assert_eq!(first.get_board_dim(), LEVEL_BOARD_DIM);
+
+        // pinned so a change to the generator, or to rand, can't quietly hand out a different board for a past date
+        let labels: Vec<String> = first.get_tiles().iter()
+            .map(|row| row.iter().map(|&tile| tile_label(tile)).collect())
+            .collect();
+        assert_eq!(labels, ["V2VV2", "V2121", "VV21V", "131VV", "21V12"]);
+    }
+
+    // Stored ratings are what players see before they pick a puzzle so they have to be right
+    #[test]
+    fn shipped_packs_load_with_correct_ratings() {
+        let packs = Path::new(env!("CARGO_MANIFEST_DIR")).join(PACKS_DIR);
+        let puzzles = load_pack(&packs.join("starter")).unwrap();
+        assert!(puzzles.len() >= 3);
+        assert_eq!(puzzles[0].id, "starter/01");
+        for puzzle in &puzzles {
+            assert_eq!(rate_board(&puzzle.board), puzzle.difficulty, "{}", puzzle.id);
+        }
+    }
+
+    #[test]
+    fn results_append_and_rank() {
+        let dir = std::env::temp_dir().join(format!("voltorb_results_{}", std::process::id()));
+        let path = dir.join(RESULTS_PATH);
+        let _ = fs::remove_file(&path);
+        assert!(load_results(&path).unwrap().is_empty());
+
+        let result = |player: &str, puzzle: &str, result: &str, score: u32, hints_used: u32| PuzzleResult {
+            puzzle: String::from(puzzle), player: String::from(player), date: String::from("2026-10-19"),
+            result: String::from(result), score, flips: 10, hints_used,
+        };
+        let attempts = [
+            result("ash", "daily-2026-10-19", "lost", 0, 0),
+            result("misty", "daily-2026-10-19", "won", 24, 2),
+            result("brock", "daily-2026-10-19", "won", 24, 0),
+            result("ash", "starter/01", "won", 6, 0),
+        ];
+        for attempt in &attempts {
+            record_result(&path, attempt).unwrap();
+        }
+
+        let results = load_results(&path).unwrap();
+        assert_eq!(results, attempts);
+        let players: Vec<&str> = leaderboard(&results, "daily-2026-10-19").iter().map(|result| result.player.as_str()).collect();
+        assert_eq!(players, ["brock", "misty", "ash"]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pack_names_stay_inside_the_packs_dir() {
+        assert_eq!(pack_dir("starter").unwrap(), Path::new(PACKS_DIR).join("starter"));
+        for name in ["", ".", "..", "../../x", "a/b", "a/", "/tmp", "a\\b", "..\\x"] {
+            assert!(matches!(pack_dir(name), Err(PackError::BadName(_))), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn bad_board_files_are_reported() {
+        let dir = std::env::temp_dir().join(format!("voltorb_pack_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert!(matches!(load_pack(&dir), Err(PackError::Empty(_))));
+
+        fs::write(dir.join("01.json"), r#"{"solution": [["1", "?"], ["V", "2"]]}"#).unwrap();
+        let err = load_pack(&dir).err().unwrap();
+        assert!(err.to_string().contains("unknown tile \"?\""), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn generated_packs_load_in_target_order() {
+        let dir = std::env::temp_dir().join(format!("voltorb_generated_{}", std::process::id()));
+        generate_pack(&dir, &[(1, 0.0, 100.0), (2, 0.0, 100.0)], 7).unwrap();
+
+        let pack = load_pack(&dir).unwrap();
+        assert_eq!(pack.len(), 2);
+        assert!(pack[0].id.ends_with("/01"));
+        for puzzle in &pack {
+            assert_eq!(puzzle.difficulty.score, rate_board(&puzzle.board).score);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,575 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use rand::{rng, rngs::StdRng, SeedableRng};
+use serde::{Serialize, Deserialize};
+use crate::board::{level_layout, Board, TileValue, LEVEL_BOARD_DIM};
+use crate::game::{Game, GameState};
+use crate::position::{analyze_tiles, check_clues, parse_tile, AnalysisError, Position};
+use crate::protocol::{state_label, GameView};
+
+// requests bigger than this are refused instead of read into memory
+const MAX_BODY_BYTES: usize = 64 * 1024;
+// bigger boards would let one request tie up a thread and a lot of memory, the frontend stops at 8 too
+pub(crate) const MAX_GAME_SIZE: usize = 8;
+// a blank 5x5 takes the solver milliseconds, a blank 6x6 already takes it most of a minute
+const MAX_ANALYZE_HIDDEN: usize = 25;
+// games kept in memory, past this creating one evicts the oldest finished game, or the oldest of all
+const MAX_GAMES: usize = 1000;
+// a client that stops sending mid-request gets dropped instead of holding its thread forever
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct Games {
+    next_id: u64,
+    games: HashMap<u64, Game>,
+}
+
+pub type SharedGames = Arc<Mutex<Games>>;
+
+impl Games {
+    // Ids only go up, so the smallest id is the oldest game
+    fn make_room(&mut self) {
+        while self.games.len() >= MAX_GAMES {
+            let finished = self.games.iter()
+                .filter(|(_, game)| game.get_state() != GameState::InProgress)
+                .map(|(&id, _)| id)
+                .min();
+            let Some(id) = finished.or_else(|| self.games.keys().copied().min()) else {
+                return;
+            };
+            self.games.remove(&id);
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct NewGameRequest {
+    size: Option<usize>,
+    level: Option<usize>,
+    seed: Option<u64>,
+    premade: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FlipRequest {
+    row: usize,
+    col: usize,
+}
+
+#[derive(Deserialize, Debug)]
+struct MemoRequest {
+    row: usize,
+    col: usize,
+    value: String,  // "V", "1", "2" or "3"
+}
+
+#[derive(Serialize, Debug)]
+struct GameResponse {
+    id: u64,
+    #[serde(flatten)]
+    view: GameView,
+}
+
+#[derive(Serialize, Debug)]
+struct QuitResponse {
+    id: u64,
+    score: u32,
+    result: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ErrorResponse {
+    error: String,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+// Status code, content type and body
+type Reply = (u16, &'static str, String);
+
+// The browser frontend, baked into the binary so `serve` needs no files next to it
+const INDEX_HTML: &str = include_str!("../assets/index.html");
+const APP_JS: &str = include_str!("../assets/app.js");
+
+// Blocks forever, one thread per connection, games live in memory until they're quit or evicted
+pub fn serve(addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("Serving Voltorb Flip on http://{}", listener.local_addr()?);
+    run(listener, SharedGames::default());
+    Ok(())
+}
+
+pub fn run(listener: TcpListener, games: SharedGames) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let games = Arc::clone(&games);
+        thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &games) {
+                eprintln!("Connection error: {}", err);
+            }
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, games: &SharedGames) -> io::Result<()> {
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+    let (status, content_type, body) = match read_request(&mut stream)? {
+        Ok(request) => route(&request, games),
+        Err(reply) => reply,
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason(status), content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+// The outer error is the socket failing, the inner one is a malformed request we still answer
+fn read_request(stream: &mut TcpStream) -> io::Result<Result<Request, Reply>> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Err(error(400, "malformed request line")));
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok(Err(error(400, "bad Content-Length"))),
+                }
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return Ok(Err(error(413, "request body too large")));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Err(error(400, "body must be UTF-8")));
+    };
+
+    // query strings aren't used by any endpoint
+    let path = path.split('?').next().unwrap_or(path).to_string();
+    Ok(Ok(Request { method: method.to_string(), path, body }))
+}
+
+fn route(request: &Request, games: &SharedGames) -> Reply {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", [""]) | ("GET", ["index.html"]) => (200, "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        ("GET", ["app.js"]) => (200, "text/javascript; charset=utf-8", APP_JS.to_string()),
+        ("POST", ["games"]) => create_game(&request.body, games),
+        ("POST", ["analyze"]) => analyze(&request.body),
+        (method, ["games", id, rest @ ..]) => {
+            let Ok(id) = id.parse::<u64>() else {
+                return error(404, "game ids are numbers");
+            };
+            match (method, rest) {
+                ("GET", []) => with_game(games, id, |game| ok(200, &GameResponse { id, view: GameView::of(game) })),
+                ("POST", ["flip"]) => flip(&request.body, games, id),
+                ("POST", ["quit"]) => quit(games, id),
+                ("POST", ["memo"]) => memo(&request.body, games, id),
+                ("GET", ["analysis"]) => game_analysis(games, id),
+                _ => error(404, "no such endpoint"),
+            }
+        }
+        _ => error(404, "no such endpoint"),
+    }
+}
+
+fn create_game(body: &str, games: &SharedGames) -> Reply {
+    // an empty body means a random board of the default size
+    let request: NewGameRequest = if body.trim().is_empty() {
+        NewGameRequest::default()
+    } else {
+        match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(err) => return error(400, &format!("bad game request: {}", err)),
+        }
+    };
+
+    let game = match new_game(&request) {
+        Ok(game) => game,
+        Err(message) => return error(400, &message),
+    };
+
+    let mut games = games.lock().expect("Game table poisoned");
+    games.make_room();
+    let id = games.next_id;
+    games.next_id += 1;
+    let reply = ok(201, &GameResponse { id, view: GameView::of(&game) });
+    games.games.insert(id, game);
+    reply
+}
+
+fn new_game(request: &NewGameRequest) -> Result<Game, String> {
+    if let Some(index) = request.premade {
+        let premade_boards = Board::premade_boards();
+        let Some(board) = premade_boards.get(index) else {
+            return Err(format!("there are only {} premade boards", premade_boards.len()));
+        };
+        return Game::try_new(board.get_board_dim(), Some(board.clone()), None, None, None).map_err(|err| err.to_string());
+    }
+
+    let size = request.size.unwrap_or(LEVEL_BOARD_DIM);
+    if size == 0 || size > MAX_GAME_SIZE {
+        return Err(format!("size must be between 1 and {}", MAX_GAME_SIZE));
+    }
+
+    let (num_twos, num_threes, num_voltorbs) = match request.level {
+        None => (None, None, None),
+        Some(_) if size != LEVEL_BOARD_DIM => {
+            return Err(format!("levels only exist for {}x{} boards", LEVEL_BOARD_DIM, LEVEL_BOARD_DIM));
+        }
+        Some(level) => {
+            let layout = match request.seed {
+                Some(seed) => level_layout(&mut StdRng::seed_from_u64(seed), level),
+                None => level_layout(&mut rng(), level),
+            };
+            let Some((twos, threes, voltorbs)) = layout else {
+                return Err(String::from("level must be between 1 and 8"));
+            };
+            (Some(twos), Some(threes), Some(voltorbs))
+        }
+    };
+
+    match request.seed {
+        Some(seed) => Game::try_new_seeded(size, seed, num_twos, num_threes, num_voltorbs),
+        None => Game::try_new(size, None, num_twos, num_threes, num_voltorbs),
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn flip(body: &str, games: &SharedGames, id: u64) -> Reply {
+    let FlipRequest { row, col } = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(err) => return error(400, &format!("bad flip request: {}", err)),
+    };
+
+    with_game(games, id, |game| {
+        let size = game.curr_board.get_board_dim();
+        if row >= size || col >= size {
+            return error(400, &format!("({},{}) is off the {}x{} board", row, col, size, size));
+        }
+        if game.get_state() != GameState::InProgress {
+            return error(409, "the game is over");
+        }
+        if game.curr_board.get_val(row, col) != TileValue::Hidden {
+            return error(409, &format!("({},{}) is already flipped", row, col));
+        }
+        game.click(row, col);
+        ok(200, &GameResponse { id, view: GameView::of(game) })
+    })
+}
+
+fn quit(games: &SharedGames, id: u64) -> Reply {
+    let mut games = games.lock().expect("Game table poisoned");
+    match games.games.remove(&id) {
+        Some(game) => ok(200, &QuitResponse {
+            id,
+            score: game.get_score(),
+            result: state_label(game.get_state()).to_string(),
+        }),
+        None => error(404, &format!("no game with id {}", id)),
+    }
+}
+
+fn memo(body: &str, games: &SharedGames, id: u64) -> Reply {
+    let MemoRequest { row, col, value } = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(err) => return error(400, &format!("bad memo request: {}", err)),
+    };
+    let Some(val) = parse_tile(&value).filter(|&val| val != TileValue::Hidden) else {
+        return error(400, &format!("unknown memo {:?}, use \"V\", \"1\", \"2\" or \"3\"", value));
+    };
+
+    with_game(games, id, |game| {
+        let size = game.curr_board.get_board_dim();
+        if row >= size || col >= size {
+            return error(400, &format!("({},{}) is off the {}x{} board", row, col, size, size));
+        }
+        game.toggle_memo(row, col, val);
+        ok(200, &GameResponse { id, view: GameView::of(game) })
+    })
+}
+
+// Heatmap for a game in progress, counts as a hint like the CLI's.
+// The position is copied out first so the solver doesn't hold the lock every other game needs.
+fn game_analysis(games: &SharedGames, id: u64) -> Reply {
+    let (tiles, rows, cols) = {
+        let mut games = games.lock().expect("Game table poisoned");
+        let Some(game) = games.games.get_mut(&id) else {
+            return error(404, &format!("no game with id {}", id));
+        };
+        if game.get_state() != GameState::InProgress {
+            return error(409, "the game is over");
+        }
+        let hidden = game.curr_board.get_hidden_tile_indices().len();
+        if hidden > MAX_ANALYZE_HIDDEN {
+            return error(422, &format!("{} hidden tiles is too many to analyze, the limit is {}", hidden, MAX_ANALYZE_HIDDEN));
+        }
+        // same checks /analyze runs on a posted position, before the hint is spent
+        if let Err(err) = check_clues(&game.row_sums, &game.col_sums) {
+            return error(422, &err.to_string());
+        }
+        game.use_hint();
+        (game.curr_board.get_tiles().clone(), game.row_sums.clone(), game.col_sums.clone())
+    };
+
+    match analyze_tiles(&tiles, &rows, &cols) {
+        Ok(analysis) => ok(200, &analysis),
+        Err(err) => error(422, &err.to_string()),
+    }
+}
+
+fn analyze(body: &str) -> Reply {
+    let position: Position = match serde_json::from_str(body) {
+        Ok(position) => position,
+        Err(err) => return error(400, &format!("bad analyze request: {}", err)),
+    };
+    let (tiles, rows, cols) = match position.parse() {
+        Ok(parsed) => parsed,
+        Err(err) => return error(400, &err.to_string()),
+    };
+    let hidden = tiles.iter().flatten().filter(|&&tile| tile == TileValue::Hidden).count();
+    if hidden > MAX_ANALYZE_HIDDEN {
+        return error(400, &format!("{} hidden tiles is too many to analyze, the limit is {}", hidden, MAX_ANALYZE_HIDDEN));
+    }
+    match analyze_tiles(&tiles, &rows, &cols) {
+        Ok(analysis) => ok(200, &analysis),
+        Err(err @ AnalysisError::BadPosition(_)) => error(400, &err.to_string()),
+        Err(err) => error(422, &err.to_string()),
+    }
+}
+
+fn with_game(games: &SharedGames, id: u64, action: impl FnOnce(&mut Game) -> Reply) -> Reply {
+    let mut games = games.lock().expect("Game table poisoned");
+    match games.games.get_mut(&id) {
+        Some(game) => action(game),
+        None => error(404, &format!("no game with id {}", id)),
+    }
+}
+
+fn ok<T: Serialize>(status: u16, body: &T) -> Reply {
+    (status, "application/json", serde_json::to_string(body).expect("Failed to serialize JSON"))
+}
+
+fn error(status: u16, message: &str) -> Reply {
+    ok(status, &ErrorResponse { error: message.to_string() })
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    // Server on a free local port, running until the test process exits
+    fn start() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || run(listener, SharedGames::default()));
+        addr
+    }
+
+    // Minimal HTTP client, returns the status code, content type and raw body
+    fn request_raw(addr: &str, method: &str, path: &str, body: &str) -> (u16, String, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\n\r\n{}", method, path, addr, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let content_type = head.lines()
+            .find_map(|line| line.strip_prefix("Content-Type: "))
+            .unwrap_or_default()
+            .to_string();
+        (status, content_type, body.to_string())
+    }
+
+    fn request(addr: &str, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let (status, _, body) = request_raw(addr, method, path, body);
+        (status, serde_json::from_str(&body).unwrap())
+    }
+
+    #[test]
+    fn games_can_be_created_flipped_and_quit() {
+        let addr = start();
+        let (status, created) = request(&addr, "POST", "/games", r#"{"level": 3, "seed": 11}"#);
+        assert_eq!(status, 201);
+        assert_eq!(created["size"], 5);
+        let id = created["id"].as_u64().unwrap();
+
+        let (status, flipped) = request(&addr, "POST", &format!("/games/{}/flip", id), r#"{"row": 2, "col": 3}"#);
+        assert_eq!(status, 200);
+        assert_ne!(flipped["board"][2][3], "?");
+
+        let (status, state) = request(&addr, "GET", &format!("/games/{}", id), "");
+        assert_eq!(status, 200);
+        assert_eq!(state["board"], flipped["board"]);
+        assert!(state.get("solution").is_none());
+
+        let (status, _) = request(&addr, "POST", &format!("/games/{}/quit", id), "");
+        assert_eq!(status, 200);
+        let (status, _) = request(&addr, "GET", &format!("/games/{}", id), "");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn seeded_level_games_are_reproducible_and_keyed_apart() {
+        let addr = start();
+        let (_, first) = request(&addr, "POST", "/games", r#"{"level": 8, "seed": 4}"#);
+        let (_, second) = request(&addr, "POST", "/games", r#"{"level": 8, "seed": 4}"#);
+        assert_ne!(first["id"], second["id"]);
+        assert_eq!(first["row_clues"], second["row_clues"]);
+        assert_eq!(first["col_clues"], second["col_clues"]);
+
+        let voltorbs: u64 = first["row_clues"].as_array().unwrap().iter().map(|clue| clue["voltorbs"].as_u64().unwrap()).sum();
+        assert_eq!(voltorbs, 10);
+    }
+
+    #[test]
+    fn analyze_recommends_a_tile_for_a_posted_position() {
+        let addr = start();
+        let body = r#"{
+            "board": [["?", "?", "?"], ["?", "?", "?"], ["?", "?", "?"]],
+            "row_clues": [{"sum": 3, "voltorbs": 1}, {"sum": 5, "voltorbs": 0}, {"sum": 3, "voltorbs": 0}],
+            "col_clues": [{"sum": 2, "voltorbs": 1}, {"sum": 5, "voltorbs": 0}, {"sum": 4, "voltorbs": 0}]
+        }"#;
+        let (status, analysis) = request(&addr, "POST", "/analyze", body);
+        assert_eq!(status, 200);
+        assert_eq!(analysis["probability"], 1.0);
+        assert_eq!(analysis["safety"][0][0], 0.0);
+        assert!(analysis["explanation"][0].as_str().unwrap().contains("guaranteed safe"));
+    }
+
+    #[test]
+    fn frontend_assets_are_served() {
+        let addr = start();
+        let (status, content_type, page) = request_raw(&addr, "GET", "/", "");
+        assert_eq!(status, 200);
+        assert!(content_type.starts_with("text/html"));
+        assert!(page.contains("/app.js"));
+
+        let (status, content_type, script) = request_raw(&addr, "GET", "/app.js", "");
+        assert_eq!(status, 200);
+        assert!(content_type.starts_with("text/javascript"));
+        assert!(script.contains("/analysis"));
+    }
+
+    #[test]
+    fn memos_and_heatmap_for_a_game() {
+        let addr = start();
+        let (_, created) = request(&addr, "POST", "/games", r#"{"premade": 0}"#);
+        let id = created["id"].as_u64().unwrap();
+
+        let (status, noted) = request(&addr, "POST", &format!("/games/{}/memo", id), r#"{"row": 1, "col": 1, "value": "V"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(noted["memos"][1][1], serde_json::json!(["V"]));
+        assert_eq!(request(&addr, "POST", &format!("/games/{}/memo", id), r#"{"row": 1, "col": 1, "value": "?"}"#).0, 400);
+
+        let (status, analysis) = request(&addr, "GET", &format!("/games/{}/analysis", id), "");
+        assert_eq!(status, 200);
+        assert_eq!(analysis["safety"].as_array().unwrap().len(), 5);
+        let (_, state) = request(&addr, "GET", &format!("/games/{}", id), "");
+        assert_eq!(state["hints_used"], 1);
+
+        // too big to analyze, and refusing doesn't cost a hint
+        let (_, big) = request(&addr, "POST", "/games", r#"{"size": 6, "seed": 1}"#);
+        let big_id = big["id"].as_u64().unwrap();
+        assert_eq!(request(&addr, "GET", &format!("/games/{}/analysis", big_id), "").0, 422);
+        assert_eq!(request(&addr, "GET", &format!("/games/{}", big_id), "").1["hints_used"], 0);
+    }
+
+    #[test]
+    fn bad_requests_are_rejected() {
+        let addr = start();
+        assert_eq!(request(&addr, "POST", "/games", r#"{"size": 4, "level": 2}"#).0, 400);
+        assert_eq!(request(&addr, "POST", "/games", r#"{"level": 9}"#).0, 400);
+        assert_eq!(request(&addr, "POST", "/games/99/flip", r#"{"row": 0, "col": 0}"#).0, 404);
+        assert_eq!(request(&addr, "DELETE", "/games", "").0, 404);
+        assert_eq!(request(&addr, "POST", "/analyze", r#"{"board": [["?"]], "row_clues": [], "col_clues": []}"#).0, 400);
+        // clues no line can have used to overflow the solver's u8 sums
+        let (status, body) = request(&addr, "POST", "/analyze",
+            r#"{"board": [["?"]], "row_clues": [{"sum": 255, "voltorbs": 255}], "col_clues": [{"sum": 255, "voltorbs": 255}]}"#);
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("doesn't fit"));
+    }
+
+    #[test]
+    fn oversized_boards_are_refused() {
+        let addr = start();
+        assert_eq!(request(&addr, "POST", "/games", &format!(r#"{{"size": {}}}"#, MAX_GAME_SIZE)).0, 201);
+        assert_eq!(request(&addr, "POST", "/games", &format!(r#"{{"size": {}}}"#, MAX_GAME_SIZE + 1)).0, 400);
+        assert_eq!(request(&addr, "POST", "/games", r#"{"size": 100000}"#).0, 400);
+
+        // a blank 6x6 would keep the solver busy for most of a minute
+        let position = serde_json::json!({
+            "board": vec![vec!["?"; 6]; 6],
+            "row_clues": vec![serde_json::json!({"sum": 6, "voltorbs": 1}); 6],
+            "col_clues": vec![serde_json::json!({"sum": 6, "voltorbs": 1}); 6],
+        });
+        let (status, body) = request(&addr, "POST", "/analyze", &position.to_string());
+        assert_eq!(status, 400);
+        assert!(body["error"].as_str().unwrap().contains("36 hidden tiles"));
+    }
+
+    #[test]
+    fn full_game_table_evicts_finished_games_first() {
+        let games = SharedGames::default();
+        for _ in 0..MAX_GAMES {
+            assert_eq!(create_game(r#"{"size": 1, "seed": 1}"#, &games).0, 201);
+        }
+        // finish game 5, it goes before the older game 0 that's still being played
+        let finished = (0..).map(|seed| Game::new_seeded(1, seed, None, None, None))
+            .find_map(|mut game| (game.click(0, 0) != GameState::InProgress).then_some(game))
+            .unwrap();
+        games.lock().unwrap().games.insert(5, finished);
+
+        create_game("", &games);
+        create_game("", &games);
+        let games = games.lock().unwrap();
+        assert_eq!(games.games.len(), MAX_GAMES);
+        assert!(!games.games.contains_key(&5) && !games.games.contains_key(&0));
+        assert!(games.games.contains_key(&1) && games.games.contains_key(&(MAX_GAMES as u64 + 1)));
+    }
+}
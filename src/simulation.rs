@@ -1,41 +1,168 @@
-use std::fs::File;
+use std::fs;
 use std::io::{Write, stdout};
+use std::path::Path;
 use std::time::Instant;
 use rand::seq::SliceRandom;
 use serde::{Serialize, Deserialize};
 use crate::board::Board;
-use crate::game::Game;
+use crate::board::TileValue;
+use crate::game::{Game, GameState};
+use crate::charts::{bar_chart, line_chart, save_svg, Series};
+use crate::sinks::{open_sink, write_json, CsvRecord, OutputConfig, OutputFormat};
 use crate::solver::{exhaustive, optimized_solver};
+use crate::stress::{load_corpus, CORPUS_PATH};
+
+pub type Solver = fn(&mut Game) -> ((usize, usize), f32);
 
 #[derive(Serialize, Deserialize, Debug)]
 struct SimulationResult {
     board_index: usize,
+    #[serde(default)]
+    board_size: usize,
     best_move: (usize, usize),
     probability: f32,
     execution_time_ms: f64,
 }
 
-pub fn run_simulation() {
+#[derive(Serialize, Deserialize, Debug)]
+struct TimingSummary {
+    mean_ms: f64,
+    median_ms: f64,
+    p95_ms: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct HistogramBucket {
+    low: f32,
+    high: f32,
+    count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BoardSizeSummary {
+    board_size: usize,
+    boards: usize,
+    execution_time: TimingSummary,
+    // over the boards the solver found a move on, like the histogram, failures only count in failure_rate
+    mean_probability: f32,
+    failure_rate: f64,
+}
+
+// Aggregate statistics for one batch of SimulationResults, saved next to the raw data
+#[derive(Serialize, Deserialize, Debug)]
+struct RunSummary {
+    boards: usize,
+    execution_time: TimingSummary,
+    probability_histogram: Vec<HistogramBucket>,
+    failures: usize,
+    failure_rate: f64,
+    by_board_size: Vec<BoardSizeSummary>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AutoplayResult {
+    board_index: usize,
+    won: bool,
+    coins: u32,
+    flips: usize,
+    forced_guesses: usize,
+    execution_time_ms: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TournamentStanding {
+    solver: String,
+    games: usize,
+    wins: usize,
+    win_rate: f64,
+    avg_coins: f64,
+    results: Vec<AutoplayResult>,
+}
+// Mean of the per-board differences (a - b) with a 95% confidence interval, zero-width when every board differs the same
+// Mean of the per-board differences (a - b) with a 95% confidence interval
+#[derive(Serialize, Deserialize, Debug)]
+struct PairedDifference {
+    mean: f64,
+    std_error: f64,
+    ci_low: f64,
+    ci_high: f64,
+    significant: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PairedComparison {
+    solver_a: String,
+    solver_b: String,
+    win_rate_diff: Option<PairedDifference>,  // None when fewer than 2 boards were played
+    coins_diff: Option<PairedDifference>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TournamentReport {
+    board_size: usize,
+    num_boards: usize,
+    seed: u64,
+    standings: Vec<TournamentStanding>,
+    comparisons: Vec<PairedComparison>,
+}
+
+impl CsvRecord for SimulationResult {
+    fn csv_header() -> &'static str {
+        "board_index,board_size,best_move_row,best_move_col,probability,execution_time_ms"
+    }
+
+    fn csv_row(&self) -> String {
+        format!("{},{},{},{},{},{}",
+            self.board_index, self.board_size, self.best_move.0, self.best_move.1,
+            self.probability, self.execution_time_ms)
+    }
+}
+
+impl CsvRecord for AutoplayResult {
+    fn csv_header() -> &'static str {
+        "board_index,won,coins,flips,forced_guesses,execution_time_ms"
+    }
+
+    fn csv_row(&self) -> String {
+        format!("{},{},{},{},{},{}",
+            self.board_index, self.won, self.coins, self.flips,
+            self.forced_guesses, self.execution_time_ms)
+    }
+}
+
+pub fn run_simulation(output: &OutputConfig) {
     // println!("Running premade boards...");
-    // run_premade_boards();
+    // run_premade_boards(output);
     // println!("Running long premade test...");
-    // run_long_premade();
+    // run_long_premade(output);
+    // println!("Running stress corpus...");
+    // run_stress_corpus(output);
     // println!("Running random boards...");
-    // run_random_boards(100, 3); // Run 100 random 3x3 boards
+    // run_random_boards(100, 3, output); // Run 100 random 3x3 boards
     // for i in 4..7 {
     //     println!("Running random {}-dimension boards on optimal solver...", i);
-    //     run_rand_opt(100, i);
+    //     run_rand_opt(100, i, output);
     // }
 
-    println!("Running half-complete test...");
-    run_half_completed_boards(1000, 5);
+    // println!("Running half-complete test...");
+    // run_half_completed_boards(1000, 5, output);
+
+    println!("Running autoplay on optimized solver...");
+    run_autoplay(100, 4, "opt", optimized_solver, output);
+
+    // println!("Running exhaustive vs optimized tournament...");
+    // run_tournament(100, 3, 0, &[("ex", exhaustive), ("opt", optimized_solver)], output);
+
+    regenerate_charts(output);
 }
 
 // Runs simulation on premade boards
-fn run_premade_boards() {
+pub fn run_premade_boards(output: &OutputConfig) {
     let premade_boards = Board::premade_boards();
     let mut ex_results = Vec::new();
     let mut opt_results = Vec::new();
+    let mut ex_sink = open_sink(output, "ex_premade");
+    let mut opt_sink = open_sink(output, "opt_premade");
 
     print!("Running Premade Board at index: ");
     stdout().flush().unwrap();
@@ -60,30 +187,40 @@ fn run_premade_boards() {
         let elapsed_time_opt = start_time.elapsed().as_secs_f64() * 1000.0;
 
         // Prepare results
-        ex_results.push(SimulationResult {
+        let ex_result = SimulationResult {
             board_index,
+            board_size: ex_game.curr_board.get_board_dim(),
             best_move: best_move_ex.0,
             probability: best_move_ex.1,
             execution_time_ms: elapsed_time_ex,
-        });
+        };
+        ex_sink.write(&ex_result);
+        ex_results.push(ex_result);
 
-        opt_results.push(SimulationResult {
+        let opt_result = SimulationResult {
             board_index,
+            board_size: opt_game.curr_board.get_board_dim(),
             best_move: best_move_opt.0,
             probability: best_move_opt.1,
             execution_time_ms: elapsed_time_opt,
-        });
+        };
+        opt_sink.write(&opt_result);
+        opt_results.push(opt_result);
     }
     // Save results
-    save_to_json("ex_premade.json", &ex_results);
-    save_to_json("opt_premade.json", &opt_results);
+    ex_sink.finish();
+    save_summary(output, "ex_premade", &ex_results);
+    opt_sink.finish();
+    save_summary(output, "opt_premade", &opt_results);
 }
 
 // Runs optimized solver **only** on premade boards
-fn run_long_premade() {
+pub fn run_long_premade(output: &OutputConfig) {
     let premade_boards = Board::premade_boards();
     let mut ex_results = Vec:: new();
     let mut opt_results = Vec::new();
+    let mut ex_sink = open_sink(output, "long_ex_premade");
+    let mut opt_sink = open_sink(output, "long_opt_premade");
 
     let board_index = 4;
     let premade_board = match premade_boards.get(board_index) {
@@ -112,31 +249,84 @@ fn run_long_premade() {
     let elapsed_time_opt = start_time_opt.elapsed().as_secs_f64() * 1000.0;
 
     // Store results
-    ex_results.push(SimulationResult {
+    let ex_result = SimulationResult {
         board_index,
+        board_size: ex_game.curr_board.get_board_dim(),
         best_move: best_move_ex.0,
         probability: best_move_ex.1,
         execution_time_ms: elapsed_time_ex,
-    });
+    };
+    ex_sink.write(&ex_result);
+    ex_results.push(ex_result);
 
-    opt_results.push(SimulationResult {
+    let opt_result = SimulationResult {
         board_index,
+        board_size: opt_game.curr_board.get_board_dim(),
         best_move: best_move_opt.0,
         probability: best_move_opt.1,
         execution_time_ms: elapsed_time_opt,
-    });
+    };
+    opt_sink.write(&opt_result);
+    opt_results.push(opt_result);
+
+    // Save results
+    ex_sink.finish();
+    save_summary(output, "long_ex_premade", &ex_results);
+    opt_sink.finish();
+    save_summary(output, "long_opt_premade", &opt_results);
+
+    println!("Done! Results saved to '{}' & '{}'.",
+        output.path_for("long_ex_premade").display(),
+        output.path_for("long_opt_premade").display());
+}
+
+// Times the optimized solver on every board of the stress corpus, see stress.rs.
+// Returns the slowest time in ms so callers can hold it to SOLVE_BUDGET_MS, None if the corpus couldn't be read.
+pub fn run_stress_corpus(output: &OutputConfig) -> Option<f64> {
+    let corpus = match load_corpus(Path::new(CORPUS_PATH)) {
+        Ok(corpus) => corpus,
+        Err(err) => {
+            println!("Error: couldn't load {}: {}", CORPUS_PATH, err);
+            return None;
+        }
+    };
+    let mut opt_results = Vec::new();
+    let mut opt_sink = open_sink(output, "opt_stress");
+
+    print!("Running Optimized on stress board: ");
+    stdout().flush().unwrap();
 
-    // Save results to JSON
-    save_to_json("data/long_ex_premade.json", &ex_results);
-    save_to_json("data/long_opt_premade.json", &opt_results);
+    for (board_index, entry) in corpus.iter().enumerate() {
+        print!("{}... ", board_index);
+        stdout().flush().unwrap();
 
-    println!("Done! Results saved to 'data/long_ex_premade.json' & 'data/long_opt_premade.json'.");
+        let mut opt_game = Game::new(entry.solution.len(), Some(entry.premade()), None, None, None);
+        let start_time = Instant::now();
+        let best_move_opt = optimized_solver(&mut opt_game);
+        let elapsed_time_opt = start_time.elapsed().as_secs_f64() * 1000.0;
+
+        let opt_result = SimulationResult {
+            board_index,
+            board_size: opt_game.curr_board.get_board_dim(),
+            best_move: best_move_opt.0,
+            probability: best_move_opt.1,
+            execution_time_ms: elapsed_time_opt,
+        };
+        opt_sink.write(&opt_result);
+        opt_results.push(opt_result);
+    }
+
+    opt_sink.finish();
+    save_summary(output, "opt_stress", &opt_results);
+    opt_results.iter().map(|r| r.execution_time_ms).reduce(f64::max)
 }
 
 // Runs simulation on random boards
-fn run_random_boards(num_boards: usize, board_size: usize) {
+pub fn run_random_boards(num_boards: usize, board_size: usize, output: &OutputConfig) {
     let mut ex_results = Vec::new();
     let mut opt_results = Vec::new();
+    let mut ex_sink = open_sink(output, "ex_random");
+    let mut opt_sink = open_sink(output, "opt_random");
 
     print!("Running Random Board #: ");
     stdout().flush().unwrap();
@@ -162,28 +352,38 @@ fn run_random_boards(num_boards: usize, board_size: usize) {
         let elapsed_time_opt = start_time.elapsed().as_secs_f64() * 1000.0;
 
         // Prepare results
-        ex_results.push(SimulationResult {
+        let ex_result = SimulationResult {
             board_index: i,
+            board_size,
             best_move: best_move_ex.0,
             probability: best_move_ex.1,
             execution_time_ms: elapsed_time_ex,
-        });
+        };
+        ex_sink.write(&ex_result);
+        ex_results.push(ex_result);
 
-        opt_results.push(SimulationResult {
+        let opt_result = SimulationResult {
             board_index: i,
+            board_size,
             best_move: best_move_opt.0,
             probability: best_move_opt.1,
             execution_time_ms: elapsed_time_opt,
-        });
+        };
+        opt_sink.write(&opt_result);
+        opt_results.push(opt_result);
     }
     // Save results
-    save_to_json("ex_random.json", &ex_results);
-    save_to_json("opt_random.json", &opt_results);
+    ex_sink.finish();
+    save_summary(output, "ex_random", &ex_results);
+    opt_sink.finish();
+    save_summary(output, "opt_random", &opt_results);
 }
 
 // Runs optimized on random larger boards
-fn run_rand_opt(num_boards: usize, board_size: usize) {
+pub fn run_rand_opt(num_boards: usize, board_size: usize, output: &OutputConfig) {
     let mut results = Vec::new();
+    let filename = format!("opt_random_{}x{}", board_size, board_size);
+    let mut sink = open_sink(output, &filename);
 
     print!("Running Random Board #: ");
     stdout().flush().unwrap();
@@ -203,21 +403,25 @@ fn run_rand_opt(num_boards: usize, board_size: usize) {
         let elapsed_time_opt = start_time.elapsed().as_secs_f64() * 1000.0;
 
         // Prepare results
-        results.push(SimulationResult {
+        let result = SimulationResult {
             board_index: i,
+            board_size,
             best_move: best_move_opt.0,
             probability: best_move_opt.1,
             execution_time_ms: elapsed_time_opt,
-        });
+        };
+        sink.write(&result);
+        results.push(result);
     }
     // Save results
-    let filename = format!("opt_random_{}x{}.json", board_size, board_size);
-    save_to_json(&filename, &results);
+    sink.finish();
+    save_summary(output, &filename, &results);
 }
 
 // Run half-completed boards with optimized solver
-fn run_half_completed_boards(num_boards: usize, board_size: usize) {
+pub fn run_half_completed_boards(num_boards: usize, board_size: usize, output: &OutputConfig) {
     let mut results = Vec::new();
+    let mut sink = open_sink(output, "half_complete");
 
     print!("Running Half-Completed Boards #: ");
     stdout().flush().unwrap();
@@ -255,25 +459,491 @@ fn run_half_completed_boards(num_boards: usize, board_size: usize) {
         let elapsed_time_opt = start_time.elapsed().as_secs_f64() * 1000.0;
 
         // Prepare results
-        results.push(SimulationResult {
+        let result = SimulationResult {
             board_index: i,
+            board_size,
             best_move: best_move_opt.0,
             probability: best_move_opt.1,
             execution_time_ms: elapsed_time_opt,
+        };
+        sink.write(&result);
+        results.push(result);
+    }
+
+    // Save results
+    sink.finish();
+    save_summary(output, "half_complete", &results);
+    println!("Done! Results saved to '{}'.", output.path_for("half_complete").display());
+}
+
+// Plays random boards to completion with the given solver
+pub fn run_autoplay(num_boards: usize, board_size: usize, solver_name: &str, solver: Solver, output: &OutputConfig) {
+    let mut results = Vec::new();
+    let filename = format!("autoplay_{}_{}x{}", solver_name, board_size, board_size);
+    let mut sink = open_sink(output, &filename);
+
+    print!("Running Autoplay Board #: ");
+    stdout().flush().unwrap();
+
+    for i in 0..num_boards {
+        print!("{}... ", i);
+        stdout().flush().unwrap();
+
+        let mut game = Game::new(board_size, None, None, None, None);
+
+        let start_time = Instant::now();
+        let mut result = autoplay(&mut game, solver);
+        result.execution_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+        result.board_index = i;
+
+        sink.write(&result);
+        results.push(result);
+    }
+
+    let games = results.len().max(1) as f64;
+    let wins = results.iter().filter(|r| r.won).count();
+    let total_coins: u32 = results.iter().map(|r| r.coins).sum();
+    let total_flips: usize = results.iter().map(|r| r.flips).sum();
+    let total_guesses: usize = results.iter().map(|r| r.forced_guesses).sum();
+
+    println!();
+    println!("Win rate: {:.1}% ({}/{})", wins as f64 / games * 100.0, wins, results.len());
+    println!("Average coins: {:.2}", total_coins as f64 / games);
+    println!("Average flips per game: {:.2}", total_flips as f64 / games);
+    println!("Average forced guesses per game: {:.2}", total_guesses as f64 / games);
+
+    sink.finish();
+}
+
+// Keeps asking the solver for a move until the game is won or lost.
+// A flip counts as a forced guess when the solver isn't certain the tile is safe.
+fn autoplay(game: &mut Game, solver: Solver) -> AutoplayResult {
+    let board_dim = game.curr_board.get_board_dim();
+    let mut flips = 0;
+    let mut forced_guesses = 0;
+    let mut state = game.check_sol();
+
+    while state == GameState::InProgress && flips < board_dim * board_dim {
+        let ((r, c), probability) = solver(game);
+
+        // solver gave up or pointed at an already flipped tile, treat as a loss
+        if r >= board_dim || c >= board_dim || game.curr_board.get_val(r, c) != TileValue::Hidden {
+            state = GameState::Lost;
+            break;
+        }
+
+        if probability < 1.0 {
+            forced_guesses += 1;
+        }
+        state = game.click(r, c);
+        flips += 1;
+    }
+
+    let won = state == GameState::Won;
+    AutoplayResult {
+        board_index: 0,
+        won,
+        // coins are only paid out on a win
+        coins: if won { game.get_score() } else { 0 },
+        flips,
+        forced_guesses,
+        execution_time_ms: 0.0,
+    }
+}
+
+// Plays the same seeded boards with every solver and compares them pairwise
+pub fn run_tournament(num_boards: usize, board_size: usize, seed: u64, solvers: &[(&str, Solver)], output: &OutputConfig) {
+    let mut standings = Vec::new();
+
+    for &(solver_name, solver) in solvers {
+        print!("Running Tournament for {} on Board #: ", solver_name);
+        stdout().flush().unwrap();
+
+        let mut results = Vec::new();
+        for i in 0..num_boards {
+            print!("{}... ", i);
+            stdout().flush().unwrap();
+
+            // board i is identical for every solver
+            let mut game = Game::new_seeded(board_size, seed.wrapping_add(i as u64), None, None, None);
+
+            let start_time = Instant::now();
+            let mut result = autoplay(&mut game, solver);
+            result.execution_time_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            result.board_index = i;
+
+            results.push(result);
+        }
+        println!();
+
+        let games = results.len();
+        let wins = results.iter().filter(|r| r.won).count();
+        let total_coins: u32 = results.iter().map(|r| r.coins).sum();
+        standings.push(TournamentStanding {
+            solver: solver_name.to_string(),
+            games,
+            wins,
+            win_rate: wins as f64 / games.max(1) as f64,
+            avg_coins: total_coins as f64 / games.max(1) as f64,
+            results,
         });
     }
 
-    // Save results to JSON
-    save_to_json("half_complete.json", &results);
-    println!("Done! Results saved to 'data/rand_half_complete.json'.");
+    let mut comparisons = Vec::new();
+    for a in 0..standings.len() {
+        for b in (a + 1)..standings.len() {
+            let wins_a: Vec<f64> = standings[a].results.iter().map(|r| r.won as u8 as f64).collect();
+            let wins_b: Vec<f64> = standings[b].results.iter().map(|r| r.won as u8 as f64).collect();
+            let coins_a: Vec<f64> = standings[a].results.iter().map(|r| r.coins as f64).collect();
+            let coins_b: Vec<f64> = standings[b].results.iter().map(|r| r.coins as f64).collect();
+
+            comparisons.push(PairedComparison {
+                solver_a: standings[a].solver.clone(),
+                solver_b: standings[b].solver.clone(),
+                win_rate_diff: paired_difference(&wins_a, &wins_b),
+                coins_diff: paired_difference(&coins_a, &coins_b),
+            });
+        }
+    }
+
+    println!("{:<10} {:>8} {:>10} {:>10}", "Solver", "Games", "Win rate", "Avg coins");
+    for standing in &standings {
+        println!("{:<10} {:>8} {:>9.1}% {:>10.2}",
+            standing.solver, standing.games, standing.win_rate * 100.0, standing.avg_coins);
+    }
+    for cmp in &comparisons {
+        let (Some(win_rate), Some(coins)) = (&cmp.win_rate_diff, &cmp.coins_diff) else {
+            println!("{} - {}: need at least 2 boards to compare", cmp.solver_a, cmp.solver_b);
+            continue;
+        };
+        println!("{} - {}: win rate {:+.3} [{:+.3}, {:+.3}]{}, coins {:+.2} [{:+.2}, {:+.2}]{}",
+            cmp.solver_a, cmp.solver_b,
+            win_rate.mean, win_rate.ci_low, win_rate.ci_high,
+            if win_rate.significant { " *" } else { "" },
+            coins.mean, coins.ci_low, coins.ci_high,
+            if coins.significant { " *" } else { "" });
+    }
+
+    let report = TournamentReport {
+        board_size,
+        num_boards,
+        seed,
+        standings,
+        comparisons,
+    };
+    let path = output.dir.join(format!("tournament_{}x{}.json", board_size, board_size));
+    write_json(&path, &report);
 }
 
-// Helper function to save results to JSON
-fn save_to_json(filename: &str, results: &Vec<SimulationResult>) {
-    let path = format!("data/{}", filename);
-    let json_data = serde_json::to_string_pretty(results).expect("Failed to serialize JSON");
-    let mut file = File::create(&path).expect("Failed to create file");
-    file.write_all(json_data.as_bytes()).expect("Failed to write JSON");
+// Paired-difference statistics over per-board outcomes, None with fewer than 2 boards since there's no spread to measure.
+// The interval uses Student's t so small tournaments aren't overconfident, the difference is significant when it excludes 0.
+// When every board differs by the same amount t has no spread to work with, so that case uses the exact sign test instead.
+fn paired_difference(a: &[f64], b: &[f64]) -> Option<PairedDifference> {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return None;
+    }
 
-    println!("Simulation complete. Data saved to {}", path);
+    let diffs: Vec<f64> = a.iter().zip(b).map(|(x, y)| x - y).collect();
+    let mean = diffs.iter().sum::<f64>() / n as f64;
+    let variance = diffs.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+    let std_error = (variance / n as f64).sqrt();
+    let margin = t_critical(n - 1) * std_error;
+    let ci_low = mean - margin;
+    let ci_high = mean + margin;
+
+    let significant = if std_error == 0.0 {
+        // n boards all going the same way has a two-sided p of 2 * 0.5^n, under 0.05 from 6 boards on
+        mean != 0.0 && 2.0 * 0.5f64.powi(n.min(64) as i32) < 0.05
+    } else {
+        ci_low > 0.0 || ci_high < 0.0
+    };
+
+    Some(PairedDifference { mean, std_error, ci_low, ci_high, significant })
+}
+
+// Two-sided 95% critical value of Student's t with the given degrees of freedom
+fn t_critical(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+        2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+        2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    if (1..=TABLE.len()).contains(&df) {
+        return TABLE[df - 1];
+    }
+    // past the table the Cornish-Fisher expansion around the normal value is good to 3 decimals
+    let z: f64 = 1.959964;
+    let df = df.max(1) as f64;
+    z + (z.powi(3) + z) / (4.0 * df) + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+}
+
+// Computes timing, probability and failure statistics for a batch of results
+fn summarize(results: &[SimulationResult]) -> RunSummary {
+    let is_failure = |r: &SimulationResult| r.best_move == (usize::MAX, usize::MAX);
+
+    let times: Vec<f64> = results.iter().map(|r| r.execution_time_ms).collect();
+    let failures = results.iter().filter(|r| is_failure(r)).count();
+
+    // 10 buckets of width 0.1, a probability of exactly 1.0 goes in the last one
+    let mut probability_histogram: Vec<HistogramBucket> = (0..10)
+        .map(|i| HistogramBucket { low: i as f32 / 10.0, high: (i + 1) as f32 / 10.0, count: 0 })
+        .collect();
+    for result in results.iter().filter(|r| !is_failure(r)) {
+        let bucket = ((result.probability * 10.0) as usize).min(9);
+        probability_histogram[bucket].count += 1;
+    }
+
+    let mut board_sizes: Vec<usize> = results.iter().map(|r| r.board_size).collect();
+    board_sizes.sort();
+    board_sizes.dedup();
+
+    let by_board_size = board_sizes.into_iter().map(|board_size| {
+        let group: Vec<&SimulationResult> = results.iter().filter(|r| r.board_size == board_size).collect();
+        let group_times: Vec<f64> = group.iter().map(|r| r.execution_time_ms).collect();
+        let solved: Vec<f32> = group.iter().filter(|r| !is_failure(r)).map(|r| r.probability).collect();
+        let group_failures = group.len() - solved.len();
+        BoardSizeSummary {
+            board_size,
+            boards: group.len(),
+            execution_time: summarize_times(&group_times),
+            mean_probability: solved.iter().sum::<f32>() / solved.len().max(1) as f32,
+            failure_rate: group_failures as f64 / group.len() as f64,
+        }
+    }).collect();
+
+    RunSummary {
+        boards: results.len(),
+        execution_time: summarize_times(&times),
+        probability_histogram,
+        failures,
+        failure_rate: failures as f64 / results.len().max(1) as f64,
+        by_board_size,
+    }
+}
+
+fn summarize_times(times: &[f64]) -> TimingSummary {
+    if times.is_empty() {
+        return TimingSummary { mean_ms: 0.0, median_ms: 0.0, p95_ms: 0.0 };
+    }
+
+    let mut sorted = times.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let n = sorted.len();
+    let median_ms = if n.is_multiple_of(2) {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    } else {
+        sorted[n / 2]
+    };
+    // nearest-rank percentile
+    let p95_rank = ((0.95 * n as f64).ceil() as usize).clamp(1, n);
+
+    TimingSummary {
+        mean_ms: sorted.iter().sum::<f64>() / n as f64,
+        median_ms,
+        p95_ms: sorted[p95_rank - 1],
+    }
+}
+
+// Saves the summary of a run as a companion file, e.g. opt_random.csv -> opt_random_summary.json
+fn save_summary(output: &OutputConfig, stem: &str, results: &[SimulationResult]) {
+    let summary = summarize(results);
+
+    println!("{} boards: mean {:.3} ms, median {:.3} ms, p95 {:.3} ms, failure rate {:.1}%",
+        summary.boards,
+        summary.execution_time.mean_ms,
+        summary.execution_time.median_ms,
+        summary.execution_time.p95_ms,
+        summary.failure_rate * 100.0);
+
+    let path = output.dir.join(format!("{}_summary.json", stem));
+    write_json(&path, &summary);
+}
+
+// Reads back results written by any of the sinks, trying the configured format first
+fn load_results(output: &OutputConfig, stem: &str) -> Option<Vec<SimulationResult>> {
+    let formats = [output.format, OutputFormat::Json, OutputFormat::JsonLines, OutputFormat::Csv];
+
+    for format in formats {
+        let path = output.dir.join(format!("{}.{}", stem, format.extension()));
+        let Ok(contents) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let results = match format {
+            OutputFormat::Json => serde_json::from_str(&contents).ok(),
+            OutputFormat::JsonLines => contents.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            OutputFormat::Csv => contents.lines()
+                .skip(1)
+                .filter(|line| !line.trim().is_empty())
+                .map(parse_csv_row)
+                .collect(),
+        };
+        if results.is_some() {
+            return results;
+        }
+    }
+    None
+}
+
+fn parse_csv_row(line: &str) -> Option<SimulationResult> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() != 6 {
+        return None;
+    }
+    Some(SimulationResult {
+        board_index: fields[0].parse().ok()?,
+        board_size: fields[1].parse().ok()?,
+        best_move: (fields[2].parse().ok()?, fields[3].parse().ok()?),
+        probability: fields[4].parse().ok()?,
+        execution_time_ms: fields[5].parse().ok()?,
+    })
+}
+
+// Running average of execution time after each board, same as the matplotlib runtime plots
+fn cumulative_average(results: &[SimulationResult]) -> Vec<(f64, f64)> {
+    let mut total = 0.0;
+    results.iter().enumerate().map(|(i, r)| {
+        total += r.execution_time_ms;
+        ((i + 1) as f64, total / (i + 1) as f64)
+    }).collect()
+}
+
+// Redraws the SVG charts in <output>/plots from whatever result files are present
+fn regenerate_charts(output: &OutputConfig) {
+    let plot_dir = output.dir.join("plots");
+
+    // Exhaustive vs optimized on premade boards
+    if let (Some(ex), Some(opt)) = (load_results(output, "ex_premade"), load_results(output, "opt_premade")) {
+        let categories: Vec<String> = ex.iter().map(|r| r.board_index.to_string()).collect();
+        let svg = bar_chart(
+            "Execution Time on Premade Boards",
+            "Board Index",
+            "Execution Time (ms)",
+            &categories,
+            &[
+                Series::new("Exhaustive", "blue", ex.iter().map(|r| (r.board_index as f64, r.execution_time_ms)).collect()),
+                Series::new("Optimized", "green", opt.iter().map(|r| (r.board_index as f64, r.execution_time_ms)).collect()),
+            ]);
+        save_svg(&plot_dir.join("ex_premade_comparison.svg"), &svg);
+    }
+
+    // Exhaustive vs optimized on random boards
+    if let (Some(ex), Some(opt)) = (load_results(output, "ex_random"), load_results(output, "opt_random")) {
+        let ex_avg = cumulative_average(&ex);
+        let opt_avg = cumulative_average(&opt);
+        let svg = line_chart(
+            "Average Execution Time Over Random Boards",
+            "Number of Boards Simulated",
+            "Average Execution Time (ms)",
+            &[
+                Series::new(&format!("Exhaustive (Final: {:.1} ms)", ex_avg.last().map_or(0.0, |p| p.1)), "blue", ex_avg),
+                Series::new(&format!("Optimized (Final: {:.3} ms)", opt_avg.last().map_or(0.0, |p| p.1)), "green", opt_avg),
+            ]);
+        save_svg(&plot_dir.join("ex_random_comparison.svg"), &svg);
+    }
+
+    // Optimized runtime for each board size
+    let colors = ["orange", "red", "blue", "green", "purple", "brown"];
+    let mut size_series = Vec::new();
+    for (board_size, color) in (3..=8).zip(colors) {
+        if let Some(results) = load_results(output, &format!("opt_random_{}x{}", board_size, board_size)) {
+            let avg = cumulative_average(&results);
+            let label = format!("{}x{} (Final: {:.3} ms)", board_size, board_size, avg.last().map_or(0.0, |p| p.1));
+            size_series.push(Series::new(&label, color, avg));
+        }
+    }
+    if !size_series.is_empty() {
+        let svg = line_chart(
+            "Average Execution Time by Board Size",
+            "Number of Boards Simulated",
+            "Average Execution Time (ms)",
+            &size_series);
+        save_svg(&plot_dir.join("opt_random_runtime.svg"), &svg);
+    }
+
+    // Probabilities recommended by the optimized solver across every run we have
+    let mut opt_results = Vec::new();
+    for stem in ["opt_premade", "opt_random", "half_complete"] {
+        opt_results.extend(load_results(output, stem).unwrap_or_default());
+    }
+    for board_size in 3..=8 {
+        opt_results.extend(load_results(output, &format!("opt_random_{}x{}", board_size, board_size)).unwrap_or_default());
+    }
+    if !opt_results.is_empty() {
+        let summary = summarize(&opt_results);
+        let categories: Vec<String> = summary.probability_histogram.iter()
+            .map(|b| format!("{:.1}-{:.1}", b.low, b.high))
+            .collect();
+        let counts = summary.probability_histogram.iter()
+            .enumerate()
+            .map(|(i, b)| (i as f64, b.count as f64))
+            .collect();
+        let svg = bar_chart(
+            "Recommended Move Probabilities (Optimized Solver)",
+            "Probability of Being Safe",
+            "Boards",
+            &categories,
+            &[Series::new("Optimized", "green", counts)]);
+        save_svg(&plot_dir.join("probability_histogram.svg"), &svg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paired_difference_falls_back_to_the_sign_test_without_spread() {
+        assert!(paired_difference(&[1.0], &[0.0]).is_none());
+
+        // differences 1, 2, 3: mean 2, sd 1, se 1/sqrt(3), t with 2 degrees of freedom is 4.303
+        let diff = paired_difference(&[1.0, 2.0, 3.0], &[0.0, 0.0, 0.0]).unwrap();
+        assert!((diff.mean - 2.0).abs() < 1e-9);
+        assert!((diff.std_error - 1.0 / 3f64.sqrt()).abs() < 1e-9);
+        assert!((diff.ci_low - (2.0 - 4.303 / 3f64.sqrt())).abs() < 1e-9);
+        // the normal approximation would call this significant, t doesn't
+        assert!(diff.ci_low < 0.0 && !diff.significant);
+
+        // the same gap on every board falls back to the sign test, 5 boards aren't enough for it but 10 are
+        let constant = paired_difference(&[1.0; 5], &[0.0; 5]).unwrap();
+        assert_eq!(constant.std_error, 0.0);
+        assert!(!constant.significant);
+        let sweep = paired_difference(&[1.0; 10], &[0.0; 10]).unwrap();
+        assert_eq!(sweep.std_error, 0.0);
+        assert!(sweep.significant);
+        assert!(!paired_difference(&[1.0; 10], &[1.0; 10]).unwrap().significant);
+
+        let clear = paired_difference(&[5.0, 6.0, 5.5, 6.5, 5.0, 6.0], &[0.0; 6]).unwrap();
+        assert!(clear.significant);
+    }
+
+    #[test]
+    fn summaries_leave_failed_runs_out_of_the_probabilities() {
+        let run = |board_size, best_move, probability| SimulationResult {
+            board_index: 0, board_size, best_move, probability, execution_time_ms: 1.0,
+        };
+        let failed = (usize::MAX, usize::MAX);
+        let results = [run(3, (0, 0), 0.9), run(3, (1, 1), 0.5), run(3, failed, 0.0), run(4, failed, 0.0)];
+        let summary = summarize(&results);
+
+        assert_eq!(summary.failures, 2);
+        assert_eq!(summary.probability_histogram.iter().map(|b| b.count).sum::<usize>(), 2);
+        assert!((summary.by_board_size[0].mean_probability - 0.7).abs() < 1e-6);
+        assert!((summary.by_board_size[0].failure_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.by_board_size[1].mean_probability, 0.0);
+        assert_eq!(summary.by_board_size[1].failure_rate, 1.0);
+    }
+
+    #[test]
+    fn t_critical_approaches_the_normal_value() {
+        assert_eq!(t_critical(1), 12.706);
+        assert!((t_critical(30) - 2.042).abs() < 1e-9);
+        assert!((t_critical(60) - 2.000).abs() < 1e-3);
+        assert!((t_critical(100_000) - 1.96).abs() < 1e-3);
+    }
 }
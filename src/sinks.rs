@@ -0,0 +1,157 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,       // one pretty-printed array, written when the run finishes
+    JsonLines,  // one object per line, written as each board finishes
+    Csv,        // header row then one row per board, written as each board finishes
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputConfig {
+    pub dir: PathBuf,
+    pub format: OutputFormat,
+}
+
+// Records that can be written as a CSV row
+pub trait CsvRecord {
+    fn csv_header() -> &'static str;
+    fn csv_row(&self) -> String;
+}
+
+pub trait ResultSink<T> {
+    fn write(&mut self, record: &T);
+    fn finish(&mut self);
+}
+
+struct JsonSink<T> {
+    path: PathBuf,
+    records: Vec<serde_json::Value>,
+    record_type: PhantomData<T>,
+}
+
+struct JsonLinesSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+struct CsvSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::JsonLines => "jsonl",
+            OutputFormat::Csv => "csv",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<OutputFormat> {
+        match extension {
+            "json" => Some(OutputFormat::Json),
+            "jsonl" => Some(OutputFormat::JsonLines),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+impl Default for OutputConfig {
+    fn default() -> OutputConfig {
+        OutputConfig {
+            dir: PathBuf::from("data"),
+            format: OutputFormat::Json,
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn new(dir: impl Into<PathBuf>, format: OutputFormat) -> OutputConfig {
+        OutputConfig { dir: dir.into(), format }
+    }
+
+    // e.g. "opt_random" -> data/opt_random.csv
+    pub fn path_for(&self, stem: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", stem, self.format.extension()))
+    }
+}
+
+pub fn open_sink<T: Serialize + CsvRecord + 'static>(config: &OutputConfig, stem: &str) -> Box<dyn ResultSink<T>> {
+    let path = config.path_for(stem);
+    match config.format {
+        OutputFormat::Json => Box::new(JsonSink {
+            path,
+            records: Vec::new(),
+            record_type: PhantomData,
+        }),
+        OutputFormat::JsonLines => {
+            let writer = BufWriter::new(create_file(&path));
+            Box::new(JsonLinesSink { path, writer })
+        }
+        OutputFormat::Csv => {
+            let mut writer = BufWriter::new(create_file(&path));
+            writeln!(writer, "{}", T::csv_header()).expect("Failed to write CSV header");
+            writer.flush().expect("Failed to write CSV header");
+            Box::new(CsvSink { path, writer })
+        }
+    }
+}
+
+impl<T: Serialize> ResultSink<T> for JsonSink<T> {
+    fn write(&mut self, record: &T) {
+        self.records.push(serde_json::to_value(record).expect("Failed to serialize JSON"));
+    }
+
+    fn finish(&mut self) {
+        write_json(&self.path, &self.records);
+    }
+}
+
+impl<T: Serialize> ResultSink<T> for JsonLinesSink {
+    fn write(&mut self, record: &T) {
+        let line = serde_json::to_string(record).expect("Failed to serialize JSON");
+        writeln!(self.writer, "{}", line).expect("Failed to write JSON line");
+        // flush every line so a crashed run keeps everything up to the last board
+        self.writer.flush().expect("Failed to write JSON line");
+    }
+
+    fn finish(&mut self) {
+        self.writer.flush().expect("Failed to write JSON line");
+        println!("Simulation complete. Data saved to {}", self.path.display());
+    }
+}
+
+impl<T: CsvRecord> ResultSink<T> for CsvSink {
+    fn write(&mut self, record: &T) {
+        writeln!(self.writer, "{}", record.csv_row()).expect("Failed to write CSV row");
+        self.writer.flush().expect("Failed to write CSV row");
+    }
+
+    fn finish(&mut self) {
+        self.writer.flush().expect("Failed to write CSV row");
+        println!("Simulation complete. Data saved to {}", self.path.display());
+    }
+}
+
+// Writes a single pretty-printed JSON document, creating parent directories as needed
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, data: &T) {
+    let json_data = serde_json::to_string_pretty(data).expect("Failed to serialize JSON");
+    let mut file = create_file(path);
+    file.write_all(json_data.as_bytes()).expect("Failed to write JSON");
+
+    println!("Simulation complete. Data saved to {}", path.display());
+}
+
+fn create_file(path: &Path) -> File {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).expect("Failed to create output directory");
+    }
+    File::create(path).expect("Failed to create file")
+}
@@ -2,6 +2,139 @@ use crate::board::{SumData, TileValue};
 use crate::game::Game;
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    Rule1,
+    Rule2,
+    Rule3,
+    Rule4,
+    Line,
+    Search,
+}
+
+// The clues can't all be satisfied. `tile` is the tile that ran out of values, if pruning found it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contradiction {
+    pub tile: Option<(usize, usize)>,
+    pub rule: Rule,
+}
+
+// A row or column a rule looked at, kept as an index so reasons are only formatted when someone reads them
+#[derive(Debug, Clone, Copy)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+// One pruning step that narrowed a tile's possible values, kept so the solver can explain itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deduction {
+    pub tile: (usize, usize),
+    pub rule: Rule,
+    pub reason: String,
+    pub removed: Vec<TileValue>,
+    pub depends_on: Vec<usize>,  // earlier steps this one relied on, as indices into the same trace
+}
+
+// The solver's recommended move along with the deductions that led to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub best_move: ((usize, usize), f32),
+    pub steps: Vec<Deduction>,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Rule::Rule1 => "rule1",
+            Rule::Rule2 => "rule2",
+            Rule::Rule3 => "rule3",
+            Rule::Rule4 => "rule4",
+            Rule::Line => "line",
+            Rule::Search => "search",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Row(r) => write!(f, "row {}", r),
+            Line::Column(c) => write!(f, "column {}", c),
+        }
+    }
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.tile {
+            Some((r, c)) => write!(f, "no value fits tile ({}, {}) ({})", r, c, self.rule),
+            None => write!(f, "no board satisfies every clue ({})", self.rule),
+        }
+    }
+}
+
+impl std::error::Error for Contradiction {}
+
+impl fmt::Display for Deduction {
+    // e.g. "row 2 has 0 Voltorbs left (rule1) so (2,3) cannot be a Voltorb"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = self.removed.iter()
+            .map(|&val| if val == TileValue::Voltorb { "Voltorb".to_string() } else { val.to_value().to_string() })
+            .collect();
+        let values = match names.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+            None => "anything".to_string(),
+        };
+        write!(f, "{} ({}) so ({},{}) cannot be a {}", self.reason, self.rule, self.tile.0, self.tile.1, values)
+    }
+}
+
+impl Explanation {
+    // Human-readable reasons behind the recommended tile
+    pub fn lines(&self) -> Vec<String> {
+        let ((r, c), probability) = self.best_move;
+        if (r, c) == (usize::MAX, usize::MAX) {
+            return vec!["There are no safe tiles left to flip.".to_string()];
+        }
+        if probability < 1.0 {
+            return vec![format!(
+                "No tile is guaranteed safe, ({},{}) is safe in {:.0}% of the boards that fit the clues.",
+                r, c, probability * 100.0)];
+        }
+
+        // the rules only prove it if one of them struck the Voltorb off this tile
+        let proven = self.steps.iter()
+            .any(|step| step.tile == (r, c) && step.removed.contains(&TileValue::Voltorb));
+        if !proven {
+            return vec![format!(
+                "({},{}) is guaranteed safe, found by exhaustive search: no board that fits the clues has a Voltorb there.",
+                r, c)];
+        }
+
+        // the step that struck the Voltorb plus everything it relied on, in the order they were found
+        let mut needed = vec![false; self.steps.len()];
+        let mut pending: Vec<usize> = (0..self.steps.len())
+            .filter(|&i| self.steps[i].tile == (r, c) && self.steps[i].removed.contains(&TileValue::Voltorb))
+            .collect();
+        while let Some(i) = pending.pop() {
+            if !needed[i] {
+                needed[i] = true;
+                pending.extend(&self.steps[i].depends_on);
+            }
+        }
+
+        let mut lines = vec![format!("({},{}) is guaranteed safe:", r, c)];
+        for (step, _) in self.steps.iter().zip(needed).filter(|&(_, needed)| needed) {
+            lines.push(format!("  - {}", step));
+        }
+        lines
+    }
+}
 
 
 pub fn exhaustive(game: &mut Game) -> ((usize, usize), f32) {
@@ -41,10 +174,10 @@ pub fn exhaustive(game: &mut Game) -> ((usize, usize), f32) {
             }
         }
     }
-    return (flip, safest);
+    (flip, safest)
 }
 
-fn get_unflipped_tiles(tiles: &Vec<Vec<TileValue>>) -> Vec<(usize, usize)> {
+fn get_unflipped_tiles(tiles: &[Vec<TileValue>]) -> Vec<(usize, usize)> {
     let mut unflipped: Vec<(usize, usize)> = Vec::new();
     for (r, row) in tiles.iter().enumerate() {
         for (c, &tile) in row.iter().enumerate() {
@@ -53,14 +186,12 @@ fn get_unflipped_tiles(tiles: &Vec<Vec<TileValue>>) -> Vec<(usize, usize)> {
             }
         }
     }
-    return unflipped;
+    unflipped
 }
 
-fn is_board_valid(tiles: &Vec<Vec<TileValue>>, rows: &Vec<SumData>, cols: &Vec<SumData>)
+fn is_board_valid(tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData])
                         -> bool {
-    let n = rows.len();
-
-    for r in 0..n {
+    for (r, row) in rows.iter().enumerate() {
         let mut val_sum = 0;
         let mut voltorb_cnt = 0;
         for &tile in &tiles[r] {
@@ -69,35 +200,35 @@ fn is_board_valid(tiles: &Vec<Vec<TileValue>>, rows: &Vec<SumData>, cols: &Vec<S
                 _ => val_sum += tile.to_value()
             }
         }
-        if val_sum != rows[r].value_sum || voltorb_cnt != rows[r].voltorb_count {
+        if val_sum != row.value_sum || voltorb_cnt != row.voltorb_count {
             return false;
         }
     }
-    for c in 0..cols.len() {
+    for (c, col) in cols.iter().enumerate() {
         let mut val_sum = 0;
         let mut voltorb_cnt = 0;
 
-        for r in 0..n {
-            match tiles[r][c] {
+        for row in tiles {
+            match row[c] {
                 TileValue::Voltorb => voltorb_cnt += 1,
-                _ => val_sum += tiles[r][c].to_value(),
+                _ => val_sum += row[c].to_value(),
             }
         }
 
-        if val_sum != cols[c].value_sum || voltorb_cnt != cols[c].voltorb_count {
+        if val_sum != col.value_sum || voltorb_cnt != col.voltorb_count {
             return false;
         }
     }
-    return true;
+    true
 }
 
-fn get_possible_solutions(tiles: &Vec<Vec<TileValue>>,
-                                rows: &Vec<SumData>, cols: &Vec<SumData>)
+fn get_possible_solutions(tiles: &[Vec<TileValue>],
+                                rows: &[SumData], cols: &[SumData])
                                     -> Vec<Vec<Vec<TileValue>>> {
     let unflipped_tiles = get_unflipped_tiles(tiles);
     if unflipped_tiles.is_empty() {
         if is_board_valid(tiles, rows, cols) {
-            return vec![tiles.clone()];
+            return vec![tiles.to_vec()];
         }
         else {
             return vec![];
@@ -106,20 +237,33 @@ fn get_possible_solutions(tiles: &Vec<Vec<TileValue>>,
     let mut result = Vec::new();
     let first_unflipped_tile = unflipped_tiles[0];
     for guess in [TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three] {
-        let mut new_tiles = tiles.clone();
+        let mut new_tiles = tiles.to_vec();
         new_tiles[first_unflipped_tile.0][first_unflipped_tile.1] = guess;
         result.extend(get_possible_solutions(&new_tiles, rows, cols))
     }
-    return result;
+    result
 }
 
 // ------------OPTIMIZED ALGO------------
 
 pub fn optimized_solver(game: &mut Game) -> ((usize, usize), f32) {
-    let tiles = game.curr_board.get_tiles();
-    let rows = &game.row_sums;
-    let cols = &game.col_sums;
+    // contradictions mean the clues can't be satisfied, report it like any other failure
+    try_optimized_solver(game).unwrap_or(((usize::MAX, usize::MAX), 0.0))
+}
+
+pub fn try_optimized_solver(game: &mut Game) -> Result<((usize, usize), f32), Contradiction> {
+    explain_optimized_solver(game).map(|explanation| explanation.best_move)
+}
+
+// Same search as optimized_solver but also returns every pruning step taken before the answer
+pub fn explain_optimized_solver(game: &mut Game) -> Result<Explanation, Contradiction> {
+    explain_position(game.curr_board.get_tiles(), &game.row_sums, &game.col_sums)
+}
 
+// Works straight from the clues and the tiles flipped so far, no Game or hidden solution needed
+pub fn explain_position(
+    tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData],
+) -> Result<Explanation, Contradiction> {
     let unflipped_tiles = get_unflipped_tiles(tiles);
     let mut possible_values: HashMap<(usize, usize), HashSet<TileValue>> = HashMap::new();
 
@@ -131,69 +275,148 @@ pub fn optimized_solver(game: &mut Game) -> ((usize, usize), f32) {
     }
 
     // Initialize row and column sums
-    let mut curr_row_sums = get_row_sums(tiles);
-    let mut curr_col_sums = get_col_sums(tiles);
+    let curr_row_sums = get_row_sums(tiles);
+    let curr_col_sums = get_col_sums(tiles);
+
+    let mut steps = Vec::new();
 
     // Iteratively prune until no further changes occur
     loop {
-        let (is_pruned, safe_tile) = prune(tiles, rows, &mut curr_row_sums, cols, &mut curr_col_sums, &unflipped_tiles, &mut possible_values);
+        let (is_pruned, safe_tile) = prune(tiles, rows, &curr_row_sums, cols, &curr_col_sums, &unflipped_tiles, &mut possible_values, Some(&mut steps))?;
         // println!("SAFE TILE: {}", safe_tile.0 );
         if is_pruned {
             if safe_tile != (usize::MAX, usize::MAX) {
-                // println!("dfyuiasdolfjnjksdfkslafklsnfklsanflksnflksnklfsnflknslfknsadlkfk");
-                return (safe_tile, 1.0);
+                return Ok(Explanation { best_move: (safe_tile, 1.0), steps });
             }
         }
         else {
             // println!("ENTERING EXHAUSTIVE");
-            return optimized_exhaustive(tiles, rows, &mut curr_row_sums, cols, &mut curr_col_sums, &mut possible_values);
+            let best_move = optimized_exhaustive(tiles, rows, &curr_row_sums, cols, &curr_col_sums, &mut possible_values)?;
+            return Ok(Explanation { best_move, steps });
+        }
+    }
+}
+
+// Chance each hidden tile is safe across every board that fits the clues, None for flipped tiles
+pub fn safety_grid(game: &Game) -> Result<Vec<Vec<Option<f32>>>, Contradiction> {
+    position_safety(game.curr_board.get_tiles(), &game.row_sums, &game.col_sums)
+}
+
+pub fn position_safety(
+    tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData],
+) -> Result<Vec<Vec<Option<f32>>>, Contradiction> {
+    let mut possible_values: HashMap<(usize, usize), HashSet<TileValue>> = HashMap::new();
+    for tile in get_unflipped_tiles(tiles) {
+        possible_values.insert(tile, HashSet::from([
+            TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three,
+        ]));
+    }
+    let curr_row_sums = get_row_sums(tiles);
+    let curr_col_sums = get_col_sums(tiles);
+
+    let solved_boards = opt_get_solutions(tiles, rows, &curr_row_sums, cols, &curr_col_sums, &mut possible_values);
+    if solved_boards.is_empty() {
+        return Err(Contradiction { tile: None, rule: Rule::Search });
+    }
+
+    let n = tiles.len();
+    let mut grid = vec![vec![None; n]; n];
+    for r in 0..n {
+        for c in 0..n {
+            if tiles[r][c] == TileValue::Hidden {
+                let safe_count = solved_boards.iter().filter(|board| board[r][c] != TileValue::Voltorb).count();
+                grid[r][c] = Some(safe_count as f32 / solved_boards.len() as f32);
+            }
         }
     }
+    Ok(grid)
+}
+
+// Number of boards that fit the clues and the flipped tiles, stops counting once it reaches limit
+pub fn count_solutions(
+    tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData], limit: usize,
+) -> usize {
+    let mut possible_values = full_domains(tiles);
+    let curr_row_sums = get_row_sums(tiles);
+    let curr_col_sums = get_col_sums(tiles);
+    opt_count_solutions(tiles, rows, &curr_row_sums, cols, &curr_col_sums, &mut possible_values, limit)
 }
 
+// How many candidate values the rules strike out before they stall, 0 means pruning can't get started
+pub fn pruning_progress(
+    tiles: &[Vec<TileValue>], rows: &[SumData], cols: &[SumData],
+) -> Result<usize, Contradiction> {
+    let unflipped_tiles = get_unflipped_tiles(tiles);
+    let mut possible_values = full_domains(tiles);
+    let curr_row_sums = get_row_sums(tiles);
+    let curr_col_sums = get_col_sums(tiles);
+
+    while prune(tiles, rows, &curr_row_sums, cols, &curr_col_sums, &unflipped_tiles, &mut possible_values, None)?.0 {}
+
+    let removed = possible_values.values().map(|set| 4 - set.len()).sum();
+    Ok(removed)
+}
+
+fn full_domains(tiles: &[Vec<TileValue>]) -> HashMap<(usize, usize), HashSet<TileValue>> {
+    let mut possible_values = HashMap::new();
+    for tile in get_unflipped_tiles(tiles) {
+        possible_values.insert(tile, HashSet::from([
+            TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three,
+        ]));
+    }
+    possible_values
+}
+
+#[allow(clippy::too_many_arguments)]
 fn prune(
-    tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, curr_row_sums: &Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &Vec<SumData>,
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     unflipped_tiles: &Vec<(usize, usize)>,
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) -> (bool, (usize, usize)) {
+    mut trace: Option<&mut Vec<Deduction>>,
+) -> Result<(bool, (usize, usize)), Contradiction> {
     let mut safe_tile = (usize::MAX, usize::MAX);
     let mut is_pruned = false;
 
     for &tile in unflipped_tiles {
         let old_vals = possible_values.get(&tile).cloned().unwrap_or_default();
 
-        apply_rules(tile, tiles, rows, curr_row_sums, cols, curr_col_sums, possible_values);
-
-        let updated_vals = possible_values.get(&tile).cloned().unwrap_or_default();
+        apply_rules(tile, tiles, rows, curr_row_sums, cols, curr_col_sums, possible_values, trace.as_deref_mut())?;
 
-        if updated_vals != old_vals {
+        if possible_values.get(&tile) != Some(&old_vals) {
             is_pruned = true;
-            possible_values.insert(tile, updated_vals.clone());
         }
+    }
 
-        if !updated_vals.contains(&TileValue::Voltorb) && safe_tile == (usize::MAX, usize::MAX) {
+    if propagate_lines(tiles, rows, cols, possible_values, trace)? {
+        is_pruned = true;
+    }
+
+    for &tile in unflipped_tiles {
+        let can_be_voltorb = possible_values.get(&tile).is_some_and(|set| set.contains(&TileValue::Voltorb));
+        if !can_be_voltorb {
             safe_tile = tile;
+            break;
         }
     }
-    return (is_pruned, safe_tile);
+    Ok((is_pruned, safe_tile))
 }
 
 fn optimized_exhaustive(
-    tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, curr_row_sums: &mut Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &mut Vec<SumData>,
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) -> ((usize, usize), f32) {
+) -> Result<((usize, usize), f32), Contradiction> {
     let mut safe_tiles: HashMap<(usize, usize), usize> = HashMap::new();
 
     let solved_boards = opt_get_solutions(tiles, rows, curr_row_sums, cols, curr_col_sums, possible_values);
     let num_boards = solved_boards.len();
 
-    // If no solutions exist, return default
+    // Pruning couldn't spot it but no assignment satisfies every clue
     if num_boards == 0 {
-        return ((usize::MAX, usize::MAX), 0.0);
+        return Err(Contradiction { tile: None, rule: Rule::Search });
     }
 
     let unflipped_tiles = get_unflipped_tiles(tiles);
@@ -215,33 +438,40 @@ fn optimized_exhaustive(
 
     // print_safe_tiles(&safe_tiles, num_boards);
 
+    // ties go to the first tile in reading order so the same position always gets the same advice
     for (&tile, &safe_count) in &safe_tiles {
-        if safe_count > safest {
+        if safe_count > safest || (safe_count == safest && tile < flip) {
             safest = safe_count;
             flip = tile;
         }
     }
-    return (flip, safest as f32 / num_boards as f32);
+    Ok((flip, safest as f32 / num_boards as f32))
 }
 
 fn opt_get_solutions(
-    tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, curr_row_sums: &mut Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &mut Vec<SumData>,
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
 ) -> Vec<Vec<Vec<TileValue>>> {
     let unflipped_tiles = get_unflipped_tiles(tiles);
 
     if unflipped_tiles.is_empty() {
         if is_board_valid(tiles, rows, cols) {
-            return vec![tiles.clone()];
+            return vec![tiles.to_vec()];
         } else {
             return vec![];
         }
     }
 
-    while prune(tiles, rows, curr_row_sums, cols, curr_col_sums, &unflipped_tiles, possible_values).0 {
-        // Prune modifies possible_values in place, so we don't need to update it manually
+    // Prune modifies possible_values in place, a contradiction means this branch has no solutions
+    loop {
+        // nobody reads the trace of a search branch
+        match prune(tiles, rows, curr_row_sums, cols, curr_col_sums, &unflipped_tiles, possible_values, None) {
+            Ok((true, _)) => continue,
+            Ok((false, _)) => break,
+            Err(_) => return vec![],
+        }
     }
 
     let mut result = Vec::new();
@@ -249,15 +479,15 @@ fn opt_get_solutions(
 
     if let Some(guesses) = possible_values.get(&first_unflipped_tile).cloned() {
         for guess in guesses {
-            let mut new_tiles = tiles.clone();
+            let mut new_tiles = tiles.to_vec();
             new_tiles[first_unflipped_tile.0][first_unflipped_tile.1] = guess;
 
             let mut next_possible_values = possible_values.clone();
             // Clear possible values for guessed tile
             next_possible_values.insert(first_unflipped_tile, HashSet::new());
 
-            let mut new_row_sums = curr_row_sums.clone();
-            let mut new_col_sums = curr_col_sums.clone();
+            let mut new_row_sums = curr_row_sums.to_vec();
+            let mut new_col_sums = curr_col_sums.to_vec();
 
             if guess != TileValue::Voltorb {
                 new_row_sums[first_unflipped_tile.0].value_sum += guess.to_value();
@@ -270,118 +500,253 @@ fn opt_get_solutions(
             result.extend(opt_get_solutions(
                 &new_tiles,
                 rows,
-                &mut new_row_sums,
+                &new_row_sums,
                 cols,
-                &mut new_col_sums,
+                &new_col_sums,
                 &mut next_possible_values,
             ));
         }
     }
-    return result;
+    result
 }
 
 
+// Same walk as opt_get_solutions but only counts, so huge solution spaces don't have to fit in memory
+fn opt_count_solutions(
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
+    possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
+    limit: usize,
+) -> usize {
+    let unflipped_tiles = get_unflipped_tiles(tiles);
+
+    if unflipped_tiles.is_empty() {
+        return if is_board_valid(tiles, rows, cols) { 1 } else { 0 };
+    }
+
+    loop {
+        match prune(tiles, rows, curr_row_sums, cols, curr_col_sums, &unflipped_tiles, possible_values, None) {
+            Ok((true, _)) => continue,
+            Ok((false, _)) => break,
+            Err(_) => return 0,
+        }
+    }
+
+    let mut count = 0;
+    let first_unflipped_tile = unflipped_tiles[0];
+
+    if let Some(guesses) = possible_values.get(&first_unflipped_tile).cloned() {
+        for guess in guesses {
+            if count >= limit {
+                break;
+            }
+            let mut new_tiles = tiles.to_vec();
+            new_tiles[first_unflipped_tile.0][first_unflipped_tile.1] = guess;
+
+            let mut next_possible_values = possible_values.clone();
+            next_possible_values.insert(first_unflipped_tile, HashSet::new());
+
+            let mut new_row_sums = curr_row_sums.to_vec();
+            let mut new_col_sums = curr_col_sums.to_vec();
+
+            if guess != TileValue::Voltorb {
+                new_row_sums[first_unflipped_tile.0].value_sum += guess.to_value();
+                new_col_sums[first_unflipped_tile.1].value_sum += guess.to_value();
+            } else {
+                new_row_sums[first_unflipped_tile.0].voltorb_count += 1;
+                new_col_sums[first_unflipped_tile.1].voltorb_count += 1;
+            }
+
+            count += opt_count_solutions(
+                &new_tiles,
+                rows,
+                &new_row_sums,
+                cols,
+                &new_col_sums,
+                &mut next_possible_values,
+                limit - count,
+            );
+        }
+    }
+    count.min(limit)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn apply_rules(
-    tile: (usize, usize), tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, curr_row_sums: &Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &Vec<SumData>,
+    tile: (usize, usize), tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
+    mut trace: Option<&mut Vec<Deduction>>,
+) -> Result<(), Contradiction> {
+    let before = snapshot(tile, possible_values, &trace);
+    if let Some(line) = rule1(tile, rows, curr_row_sums, cols, curr_col_sums, possible_values) {
+        record(tile, Rule::Rule1, &before, possible_values, trace.as_deref_mut(), &[], || format!("{} has 0 Voltorbs left", line));
+    }
+    check_tile(tile, Rule::Rule1, possible_values)?;
+
+    let before = snapshot(tile, possible_values, &trace);
+    if let Some(line) = rule2(tile, rows, curr_row_sums, cols, curr_col_sums, possible_values) {
+        record(tile, Rule::Rule2, &before, possible_values, trace.as_deref_mut(), &[], || format!("{} has no points left", line));
+    }
+    check_tile(tile, Rule::Rule2, possible_values)?;
+
+    let before = snapshot(tile, possible_values, &trace);
+    if let Some(line) = rule3(tile, tiles, rows, curr_row_sums, cols, curr_col_sums, possible_values) {
+        record(tile, Rule::Rule3, &before, possible_values, trace.as_deref_mut(), &[],
+            || format!("({},{}) is the last hidden tile in {}", tile.0, tile.1, line));
+    }
+    check_tile(tile, Rule::Rule3, possible_values)?;
+
+    let before = snapshot(tile, possible_values, &trace);
+    if let Some(line) = rule4(tile, tiles, rows, cols, possible_values) {
+        record(tile, Rule::Rule4, &before, possible_values, trace, &[], || format!("{} has no 2s or 3s left", line));
+    }
+    check_tile(tile, Rule::Rule4, possible_values)
+}
+
+// The tile's values before a rule runs, only needed to report what it removed
+fn snapshot(
+    tile: (usize, usize),
+    possible_values: &HashMap<(usize, usize), HashSet<TileValue>>,
+    trace: &Option<&mut Vec<Deduction>>,
+) -> HashSet<TileValue> {
+    match trace {
+        Some(_) => possible_values.get(&tile).cloned().unwrap_or_default(),
+        None => HashSet::new(),
+    }
+}
+
+// Adds a deduction to the trace if the rule removed anything from the tile, the reason is only built then.
+// `relied_on` are the other tiles whose possible values the rule used, their earlier steps become dependencies.
+fn record(
+    tile: (usize, usize), rule: Rule,
+    before: &HashSet<TileValue>,
+    possible_values: &HashMap<(usize, usize), HashSet<TileValue>>,
+    trace: Option<&mut Vec<Deduction>>,
+    relied_on: &[(usize, usize)],
+    reason: impl FnOnce() -> String,
 ) {
-    rule1(tile, rows, curr_row_sums, cols, curr_col_sums, possible_values);
-    rule2(tile, rows, curr_row_sums, cols, curr_col_sums, possible_values);
-    rule3(tile, tiles, rows, curr_row_sums, cols, curr_col_sums, possible_values);
-    rule4(tile, tiles, rows, cols, possible_values);
+    let (Some(trace), Some(after)) = (trace, possible_values.get(&tile)) else {
+        return;
+    };
+    let removed: Vec<TileValue> = [TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three]
+        .into_iter()
+        .filter(|val| before.contains(val) && !after.contains(val))
+        .collect();
+    if !removed.is_empty() {
+        let depends_on = (0..trace.len()).filter(|&i| relied_on.contains(&trace[i].tile)).collect();
+        trace.push(Deduction { tile, rule, reason: reason(), removed, depends_on });
+    }
+}
+
+// A tile with no values left means the position can't be solved
+fn check_tile(
+    tile: (usize, usize), rule: Rule,
+    possible_values: &HashMap<(usize, usize), HashSet<TileValue>>,
+) -> Result<(), Contradiction> {
+    match possible_values.get(&tile) {
+        Some(set) if set.is_empty() => Err(Contradiction { tile: Some(tile), rule }),
+        _ => Ok(()),
+    }
 }
 
-// Rule 1: Remove Voltorb if all Voltorbs are already accounted for
+// Rule 1: Remove Voltorb if all Voltorbs in the row or column are already accounted for
 fn rule1(
     (r, c): (usize, usize),
-    rows: &Vec<SumData>,
-    curr_row_sums: &Vec<SumData>,
-    cols: &Vec<SumData>,
-    curr_col_sums: &Vec<SumData>,
+    rows: &[SumData],
+    curr_row_sums: &[SumData],
+    cols: &[SumData],
+    curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) {
-    if rows[r].voltorb_count == curr_row_sums[r].voltorb_count
-        || cols[c].voltorb_count == curr_col_sums[c].voltorb_count
-    {
-        if let Some(set) = possible_values.get_mut(&(r, c)) {
-            set.remove(&TileValue::Voltorb);
-        }
+) -> Option<Line> {
+    let line = if rows[r].voltorb_count <= curr_row_sums[r].voltorb_count {
+        Line::Row(r)
+    } else if cols[c].voltorb_count <= curr_col_sums[c].voltorb_count {
+        Line::Column(c)
+    } else {
+        return None;
+    };
+
+    if let Some(set) = possible_values.get_mut(&(r, c)) {
+        set.remove(&TileValue::Voltorb);
     }
+    Some(line)
 }
 
-// Rule 2: If all sum values match, enforce Voltorb-only or clear invalid boards
+// Rule 2: If the row or column sum is already reached, the tile can only be a Voltorb.
+// When rule 1 already ruled out a Voltorb the set ends up empty and check_tile reports it.
 fn rule2(
     (r, c): (usize, usize),
-    rows: &Vec<SumData>, curr_row_sums: &Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &Vec<SumData>,
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) {
-    if rows[r].value_sum == curr_row_sums[r].value_sum
-        || cols[c].value_sum == curr_col_sums[c].value_sum
-    {
-        if let Some(set) = possible_values.get_mut(&(r, c)) {
-            if set.contains(&TileValue::Voltorb) {
-                set.clear();
-                set.insert(TileValue::Voltorb);
-            } else {
-                set.clear(); // Invalid board state
-            }
-        }
+) -> Option<Line> {
+    let line = if rows[r].value_sum <= curr_row_sums[r].value_sum {
+        Line::Row(r)
+    } else if cols[c].value_sum <= curr_col_sums[c].value_sum {
+        Line::Column(c)
+    } else {
+        return None;
+    };
+
+    if let Some(set) = possible_values.get_mut(&(r, c)) {
+        set.retain(|&val| val == TileValue::Voltorb);
     }
+    Some(line)
 }
 
-// Rule 3: If no more unflipped tiles remain in row or column, enforce exact sum
+// Rule 3: If this is the last hidden tile in its row or column, whatever is left of the
+// sum and Voltorb count decides its value
 fn rule3(
     (r, c): (usize, usize),
-    tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, curr_row_sums: &Vec<SumData>,
-    cols: &Vec<SumData>, curr_col_sums: &Vec<SumData>,
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], curr_row_sums: &[SumData],
+    cols: &[SumData], curr_col_sums: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) {
-    let mut row_unflipped_cnt = 0;
-    let mut col_unflipped_cnt = 0;
+) -> Option<Line> {
+    let row_unflipped_cnt = tiles[r].iter().filter(|&&tile| tile == TileValue::Hidden).count();
+    let col_unflipped_cnt = tiles.iter().filter(|row| row[c] == TileValue::Hidden).count();
 
-    for i in 0..tiles.len() {
-        if tiles[i][c] == TileValue::Hidden {
-            col_unflipped_cnt += 1;
-        }
+    let mut forced = Vec::new();
+    if row_unflipped_cnt == 1 {
+        forced.push((Line::Row(r), last_tile_value(rows[r], curr_row_sums[r])));
     }
-
-    for j in 0..tiles[r].len() {
-        if tiles[r][j] == TileValue::Hidden {
-            row_unflipped_cnt += 1;
-        }
+    if col_unflipped_cnt == 1 {
+        forced.push((Line::Column(c), last_tile_value(cols[c], curr_col_sums[c])));
     }
+    let &(line, _) = forced.first()?;
 
-    if row_unflipped_cnt == 0 {
-        if let Some(set) = possible_values.get_mut(&(r, c)) {
-            let val = rows[r].value_sum.saturating_sub(curr_row_sums[r].value_sum);
-            let enum_val = TileValue::to_enum(val);
-            set.clear();
-            set.insert(enum_val);
+    if let Some(set) = possible_values.get_mut(&(r, c)) {
+        for (_, value) in forced {
+            // None means no single tile can make up what's left
+            set.retain(|&val| Some(val) == value);
         }
     }
+    Some(line)
+}
 
-    if col_unflipped_cnt == 0 {
-        if let Some(set) = possible_values.get_mut(&(r, c)) {
-            let val = cols[c].value_sum.saturating_sub(curr_col_sums[c].value_sum);
-            let enum_val = TileValue::to_enum(val);
-            set.clear();
-            set.insert(enum_val);
-        }
+// The only value a line's last hidden tile can take, given its clue and what's already flipped
+fn last_tile_value(total: SumData, curr: SumData) -> Option<TileValue> {
+    let voltorbs_left = total.voltorb_count.checked_sub(curr.voltorb_count)?;
+    let sum_left = total.value_sum.checked_sub(curr.value_sum)?;
+
+    match (voltorbs_left, sum_left) {
+        (1, 0) => Some(TileValue::Voltorb),
+        (0, 1..=3) => Some(TileValue::to_enum(sum_left)),
+        _ => None,
     }
 }
 
-
 // Rule 4: Remove impossible values based on calculated reward
 fn rule4(
     (r, c): (usize, usize),
-    tiles: &Vec<Vec<TileValue>>,
-    rows: &Vec<SumData>, cols: &Vec<SumData>,
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], cols: &[SumData],
     possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
-) {
+) -> Option<Line> {
+    let mut first_line = None;
     for &is_row in &[true, false] {
         let (line_sum, voltorb_count, line_len) = if is_row {
             (rows[r].value_sum, rows[r].voltorb_count, rows.len())
@@ -389,12 +754,10 @@ fn rule4(
             (cols[c].value_sum, cols[c].voltorb_count, cols.len())
         };
 
-        let mut num_2s = 0;
-        let mut num_3s = 0;
-
-        for i in 0..line_len {
-            let tile = if is_row { tiles[r][i] } else { tiles[i][c] };
+        let mut num_2s: u16 = 0;
+        let mut num_3s: u16 = 0;
 
+        for tile in (0..line_len).map(|i| if is_row { tiles[r][i] } else { tiles[i][c] }) {
             match tile {
                 TileValue::Two => num_2s += 1,
                 TileValue::Three => num_3s += 1,
@@ -402,22 +765,125 @@ fn rule4(
             }
         }
 
-        let reward = (line_sum + voltorb_count).saturating_sub(line_len as u8 + num_2s + 2 * num_3s);
+        // u16 so a clue near u8::MAX can't overflow, Position::parse keeps them in range but Game sums aren't checked
+        let reward = (line_sum as u16 + voltorb_count as u16).saturating_sub(line_len as u16 + num_2s + 2 * num_3s);
 
         if reward == 0 {
-            for i in 0..line_len {
-                let key = if is_row { (r, i) } else { (i, c) };
+            if let Some(set) = possible_values.get_mut(&(r, c)) {
+                set.remove(&TileValue::Two);
+                set.remove(&TileValue::Three);
+            }
+            first_line = first_line.or(Some(if is_row { Line::Row(r) } else { Line::Column(c) }));
+        }
+    }
+    first_line
+}
 
-                if let Some(set) = possible_values.get_mut(&key) {
-                    set.remove(&TileValue::Two);
-                    set.remove(&TileValue::Three);
+// Line propagation: for every row and column, try every way of filling its hidden tiles from
+// their possible values that hits the remaining sum and Voltorb count exactly. Values that
+// never appear in such a filling are removed. Rows and columns are repeated until nothing changes,
+// which covers everything rules 1-4 can deduce about a single line.
+fn propagate_lines(
+    tiles: &[Vec<TileValue>],
+    rows: &[SumData], cols: &[SumData],
+    possible_values: &mut HashMap<(usize, usize), HashSet<TileValue>>,
+    mut trace: Option<&mut Vec<Deduction>>,
+) -> Result<bool, Contradiction> {
+    let n = tiles.len();
+    let mut lines: Vec<(Line, Vec<_>, SumData)> = Vec::new();
+    for (r, &clue) in rows.iter().enumerate().take(n) {
+        lines.push((Line::Row(r), (0..n).map(|c| (r, c)).collect(), clue));
+    }
+    for (c, &clue) in cols.iter().enumerate().take(n) {
+        lines.push((Line::Column(c), (0..n).map(|r| (r, c)).collect(), clue));
+    }
+
+    let mut any_pruned = false;
+    loop {
+        let mut is_pruned = false;
+
+        for (name, line, clue) in &lines {
+            let mut hidden = Vec::new();
+            let mut sum_left = clue.value_sum as i32;
+            let mut voltorbs_left = clue.voltorb_count as i32;
+            for &(r, c) in line {
+                match tiles[r][c] {
+                    TileValue::Hidden => hidden.push((r, c)),
+                    TileValue::Voltorb => voltorbs_left -= 1,
+                    tile => sum_left -= tile.to_value() as i32,
+                }
+            }
+            if hidden.is_empty() {
+                continue;
+            }
+
+            let domains: Vec<Vec<TileValue>> = hidden.iter()
+                .map(|tile| possible_values.get(tile).map(|set| set.iter().copied().collect()).unwrap_or_default())
+                .collect();
+            let mut supported: Vec<HashSet<TileValue>> = vec![HashSet::new(); hidden.len()];
+            let mut assignment = Vec::with_capacity(hidden.len());
+            find_line_fillings(&domains, sum_left, voltorbs_left, &mut assignment, &mut supported);
+
+            let reason = || format!("{} needs {} more from {} hidden tiles with {} Voltorbs left",
+                name, sum_left, hidden.len(), voltorbs_left);
+            for (&tile, values) in hidden.iter().zip(supported) {
+                if values.is_empty() {
+                    return Err(Contradiction { tile: Some(tile), rule: Rule::Line });
+                }
+                let before = possible_values.get(&tile).cloned().unwrap_or_default();
+                if before != values {
+                    possible_values.insert(tile, values);
+                    // the fillings depend on what's left for every hidden tile in the line, this one included
+                    record(tile, Rule::Line, &before, possible_values, trace.as_deref_mut(), &hidden, reason);
+                    is_pruned = true;
                 }
             }
         }
+
+        if !is_pruned {
+            return Ok(any_pruned);
+        }
+        any_pruned = true;
+    }
+}
+
+// Depth-first over the line's hidden tiles, recording every value used by a complete filling
+fn find_line_fillings(
+    domains: &[Vec<TileValue>],
+    sum_left: i32, voltorbs_left: i32,
+    assignment: &mut Vec<TileValue>,
+    supported: &mut Vec<HashSet<TileValue>>,
+) {
+    let tiles_left = (domains.len() - assignment.len()) as i32;
+
+    // each remaining non-Voltorb tile adds between 1 and 3
+    if sum_left < 0 || voltorbs_left < 0 || voltorbs_left > tiles_left {
+        return;
+    }
+    let value_tiles_left = tiles_left - voltorbs_left;
+    if sum_left < value_tiles_left || sum_left > 3 * value_tiles_left {
+        return;
+    }
+
+    if tiles_left == 0 {
+        for (i, &val) in assignment.iter().enumerate() {
+            supported[i].insert(val);
+        }
+        return;
+    }
+
+    for &val in &domains[assignment.len()] {
+        assignment.push(val);
+        if val == TileValue::Voltorb {
+            find_line_fillings(domains, sum_left, voltorbs_left - 1, assignment, supported);
+        } else {
+            find_line_fillings(domains, sum_left - val.to_value() as i32, voltorbs_left, assignment, supported);
+        }
+        assignment.pop();
     }
 }
 
-pub fn get_row_sums(tiles: &Vec<Vec<TileValue>>) -> Vec<SumData> {
+pub fn get_row_sums(tiles: &[Vec<TileValue>]) -> Vec<SumData> {
     let mut row_sums = vec![SumData { value_sum: 0, voltorb_count: 0 }; tiles.len()];
 
     for (r, row) in tiles.iter().enumerate() {
@@ -433,26 +899,355 @@ pub fn get_row_sums(tiles: &Vec<Vec<TileValue>>) -> Vec<SumData> {
             }
         }
     }
-    return row_sums;
+    row_sums
 }
 
-pub fn get_col_sums(tiles: &Vec<Vec<TileValue>>) -> Vec<SumData> {
+pub fn get_col_sums(tiles: &[Vec<TileValue>]) -> Vec<SumData> {
     let n = tiles.len();
     let mut col_sums = vec![SumData { value_sum: 0, voltorb_count: 0 }; n];
 
-    for c in 0..n {
-        for r in 0..n {
-            match tiles[r][c] {
+    for (c, sum) in col_sums.iter_mut().enumerate() {
+        for row in tiles {
+            match row[c] {
                 TileValue::One | TileValue::Two | TileValue::Three => {
-                    col_sums[c].value_sum += tiles[r][c].to_value();
+                    sum.value_sum += row[c].to_value();
                 }
                 TileValue::Voltorb => {
-                    col_sums[c].voltorb_count += 1;
+                    sum.voltorb_count += 1;
                 }
                 _ => {}
             }
         }
     }
-    return col_sums;
+    col_sums
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    const NUM_BOARDS: u64 = 300;
+
+    // Seeded board with the given tiles already flipped
+    fn position(seed: u64, board_dim: usize, revealed: &[(usize, usize)]) -> Game {
+        let mut count_rng = StdRng::seed_from_u64(seed);
+        let max_tiles = board_dim * board_dim;
+        let num_voltorbs = count_rng.random_range(1..=max_tiles / 3);
+        let num_twos = count_rng.random_range(0..=max_tiles / 4);
+        let num_threes = count_rng.random_range(0..=max_tiles / 5);
+
+        let mut game = Game::new_seeded(board_dim, seed, Some(num_twos), Some(num_threes), Some(num_voltorbs));
+        let solution = game.get_sol();
+        for &(r, c) in revealed {
+            game.curr_board.set_val(r, c, solution.get_val(r, c));
+        }
+        game
+    }
+
+    // Describes how the two solvers disagree on this position, if they do
+    fn disagreement(game: &mut Game) -> Option<String> {
+        let (ex_tile, ex_prob) = exhaustive(game);
+        let (opt_tile, opt_prob) = optimized_solver(game);
+
+        if (ex_prob - opt_prob).abs() > 1e-6 {
+            return Some(format!(
+                "exhaustive picks {:?} with p={} but optimized picks {:?} with p={}",
+                ex_tile, ex_prob, opt_tile, opt_prob));
+        }
+        if opt_prob == 1.0 && game.get_sol().get_val(opt_tile.0, opt_tile.1) == TileValue::Voltorb {
+            return Some(format!("optimized marks {:?} as safe but it is a Voltorb", opt_tile));
+        }
+
+        // every tile the grid calls certain has to be safe, not just the one the solver picks
+        let grid = match safety_grid(game) {
+            Ok(grid) => grid,
+            Err(contradiction) => return Some(format!("safety grid finds a contradiction in a real position: {}", contradiction)),
+        };
+        for (r, row) in grid.iter().enumerate() {
+            for (c, &safety) in row.iter().enumerate() {
+                if safety == Some(1.0) && game.get_sol().get_val(r, c) == TileValue::Voltorb {
+                    return Some(format!("safety grid marks {:?} as safe but it is a Voltorb", (r, c)));
+                }
+            }
+        }
+        None
+    }
+
+    // Keeps flipping extra tiles while the solvers still disagree so the reported board is as small as possible
+    fn minimise(seed: u64, board_dim: usize, mut revealed: Vec<(usize, usize)>) -> (Game, String) {
+        let mut reason = disagreement(&mut position(seed, board_dim, &revealed)).unwrap();
+        loop {
+            let hidden = position(seed, board_dim, &revealed).curr_board.get_hidden_tile_indices();
+            let smaller = hidden.into_iter().find_map(|tile| {
+                let mut candidate = revealed.clone();
+                candidate.push(tile);
+                disagreement(&mut position(seed, board_dim, &candidate)).map(|r| (candidate, r))
+            });
+            match smaller {
+                Some((candidate, candidate_reason)) => {
+                    revealed = candidate;
+                    reason = candidate_reason;
+                }
+                None => return (position(seed, board_dim, &revealed), reason),
+            }
+        }
+    }
+
+    // Solution grid with hidden tiles in brackets, followed by the clues
+    fn render(game: &Game) -> String {
+        let solution = game.get_sol();
+        let n = solution.get_board_dim();
+        let mut out = String::new();
+        for r in 0..n {
+            for c in 0..n {
+                let val = solution.get_val(r, c);
+                let symbol = if val == TileValue::Voltorb { "V".to_string() } else { val.to_value().to_string() };
+                if game.curr_board.get_val(r, c) == TileValue::Hidden {
+                    out.push_str(&format!("[{}]", symbol));
+                } else {
+                    out.push_str(&format!(" {} ", symbol));
+                }
+            }
+            out.push_str(&format!("  S:{} V:{}\n", game.row_sums[r].value_sum, game.row_sums[r].voltorb_count));
+        }
+        for c in 0..n {
+            out.push_str(&format!("S:{} V:{}  ", game.col_sums[c].value_sum, game.col_sums[c].voltorb_count));
+        }
+        out
+    }
+
+    #[test]
+    fn exhaustive_and_optimized_agree_on_small_boards() {
+        for seed in 0..NUM_BOARDS {
+            let board_dim = if seed % 2 == 0 { 3 } else { 4 };
+
+            // keep at most 6 tiles hidden so the exhaustive solver stays fast
+            let mut shuffle_rng = StdRng::seed_from_u64(seed ^ 0xF11B);
+            let mut revealed: Vec<(usize, usize)> = (0..board_dim)
+                .flat_map(|r| (0..board_dim).map(move |c| (r, c)))
+                .collect();
+            revealed.shuffle(&mut shuffle_rng);
+            revealed.truncate(board_dim * board_dim - 6);
+
+            // follow the optimized solver's advice until the board is fully flipped
+            loop {
+                let mut game = position(seed, board_dim, &revealed);
+                if game.curr_board.get_hidden_tile_indices().is_empty() {
+                    break;
+                }
+                if disagreement(&mut game).is_some() {
+                    let (smallest, reason) = minimise(seed, board_dim, revealed);
+                    panic!("solvers disagree on board from seed {}: {}\n{}", seed, reason, render(&smallest));
+                }
+
+                let (tile, _) = optimized_solver(&mut game);
+                if tile == (usize::MAX, usize::MAX) {
+                    break;
+                }
+                revealed.push(tile);
+            }
+        }
+    }
+
+    #[test]
+    fn safety_grid_matches_solver_probability() {
+        for seed in 0..50 {
+            let mut game = position(seed, 4, &[(0, 0), (1, 2), (2, 1), (3, 3)]);
+            let grid = safety_grid(&game).unwrap();
+            let (tile, probability) = optimized_solver(&mut game);
+
+            let best = grid.iter().flatten().flatten().fold(0.0f32, |best, &p| best.max(p));
+            assert_eq!(grid[tile.0][tile.1], Some(probability), "seed {}", seed);
+            assert_eq!(best, probability, "seed {}", seed);
+            assert_eq!(grid[0][0], None);
+        }
+    }
+
+    #[test]
+    fn counting_matches_enumeration_and_respects_limit() {
+        for seed in 0..30 {
+            let game = position(seed, 3, &[(0, 0), (2, 1)]);
+            let tiles = game.curr_board.get_tiles();
+            let mut possible_values = full_domains(tiles);
+            let solutions = opt_get_solutions(
+                tiles, &game.row_sums, &get_row_sums(tiles),
+                &game.col_sums, &get_col_sums(tiles), &mut possible_values).len();
+
+            assert!(solutions >= 1, "seed {}", seed);
+            assert_eq!(count_solutions(tiles, &game.row_sums, &game.col_sums, usize::MAX), solutions, "seed {}", seed);
+            assert_eq!(count_solutions(tiles, &game.row_sums, &game.col_sums, 1), 1, "seed {}", seed);
+        }
+    }
+
+    // Tiles with the given positions still hidden, plus the clues of the full solution
+    fn rule_position(solution: &[&[u8]], hidden: &[(usize, usize)]) -> (Vec<Vec<TileValue>>, Vec<SumData>, Vec<SumData>) {
+        let solved: Vec<Vec<TileValue>> = solution.iter()
+            .map(|row| row.iter().map(|&val| TileValue::to_enum(val)).collect())
+            .collect();
+        let board = Board::from_tiles(solved.clone());
+        let mut tiles = solved;
+        for &(r, c) in hidden {
+            tiles[r][c] = TileValue::Hidden;
+        }
+        (tiles, board.get_row_sums(), board.get_col_sums())
+    }
+
+    fn all_values() -> HashSet<TileValue> {
+        HashSet::from([TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three])
+    }
+
+    // Runs every rule on one tile starting from `start`
+    fn rules_on(
+        tile: (usize, usize),
+        (tiles, rows, cols): &(Vec<Vec<TileValue>>, Vec<SumData>, Vec<SumData>),
+        start: HashSet<TileValue>,
+    ) -> Result<HashSet<TileValue>, Contradiction> {
+        let mut possible_values = HashMap::from([(tile, start)]);
+        apply_rules(tile, tiles, rows, &get_row_sums(tiles), cols, &get_col_sums(tiles), &mut possible_values, None)?;
+        Ok(possible_values[&tile].clone())
+    }
+
+    #[test]
+    fn rule1_removes_voltorb_when_line_voltorbs_are_found() {
+        // row 0 has its only Voltorb flipped already
+        let position = rule_position(&[&[66, 2, 1], &[1, 66, 1], &[1, 1, 66]], &[(0, 1), (1, 1), (2, 2)]);
+        let mut possible_values = HashMap::from([((0, 1), all_values())]);
+        let (tiles, rows, cols) = &position;
+        rule1((0, 1), rows, &get_row_sums(tiles), cols, &get_col_sums(tiles), &mut possible_values);
+        assert_eq!(possible_values[&(0, 1)], HashSet::from([TileValue::One, TileValue::Two, TileValue::Three]));
+
+        // (1, 1) still has a Voltorb left in both its row and column
+        let mut possible_values = HashMap::from([((1, 1), all_values())]);
+        rule1((1, 1), rows, &get_row_sums(tiles), cols, &get_col_sums(tiles), &mut possible_values);
+        assert_eq!(possible_values[&(1, 1)], all_values());
+    }
+
+    #[test]
+    fn rules_record_why_they_removed_values() {
+        let (tiles, rows, cols) = rule_position(&[&[66, 2, 1], &[1, 66, 1], &[1, 1, 66]], &[(0, 1), (1, 1), (2, 2)]);
+        let mut possible_values = HashMap::from([((0, 1), all_values())]);
+        let mut trace = Vec::new();
+        apply_rules((0, 1), &tiles, &rows, &get_row_sums(&tiles), &cols, &get_col_sums(&tiles), &mut possible_values, Some(&mut trace)).unwrap();
+        assert_eq!(trace[0].rule, Rule::Rule1);
+        assert_eq!(trace[0].removed, vec![TileValue::Voltorb]);
+        assert_eq!(trace[0].to_string(), "row 0 has 0 Voltorbs left (rule1) so (0,1) cannot be a Voltorb");
+    }
+
+    #[test]
+    fn rule2_forces_voltorb_when_line_sum_is_reached() {
+        let position = rule_position(&[&[1, 66, 2], &[2, 1, 1], &[1, 1, 3]], &[(0, 1)]);
+        assert_eq!(rules_on((0, 1), &position, all_values()), Ok(HashSet::from([TileValue::Voltorb])));
+    }
+
+    #[test]
+    fn rule2_reports_contradiction_instead_of_clearing() {
+        // Voltorb was already excluded, so nothing can fill the tile
+        let position = rule_position(&[&[1, 66, 2], &[2, 1, 1], &[1, 1, 3]], &[(0, 1)]);
+        let start = HashSet::from([TileValue::One, TileValue::Two, TileValue::Three]);
+        assert_eq!(rules_on((0, 1), &position, start), Err(Contradiction { tile: Some((0, 1)), rule: Rule::Rule2 }));
+    }
+
+    #[test]
+    fn rule3_fills_last_tile_of_a_line() {
+        // last hidden tile in row 2 must make up the remaining 3
+        let position = rule_position(&[&[1, 1, 66], &[66, 1, 1], &[1, 1, 3]], &[(2, 2), (0, 2)]);
+        let mut possible_values = HashMap::from([((2, 2), all_values())]);
+        let (tiles, rows, cols) = &position;
+        rule3((2, 2), tiles, rows, &get_row_sums(tiles), cols, &get_col_sums(tiles), &mut possible_values);
+        assert_eq!(possible_values[&(2, 2)], HashSet::from([TileValue::Three]));
+
+        // last hidden tile in row 1 must be its Voltorb
+        let position = rule_position(&[&[1, 1, 1], &[66, 2, 1], &[1, 1, 1]], &[(1, 0)]);
+        assert_eq!(rules_on((1, 0), &position, all_values()), Ok(HashSet::from([TileValue::Voltorb])));
+    }
+
+    #[test]
+    fn rule3_rejects_leftovers_no_tile_can_hold() {
+        // row clue says 5 is still missing, which no single tile can provide
+        let (tiles, mut rows, cols) = rule_position(&[&[1, 1, 3], &[1, 1, 1], &[1, 1, 1]], &[(0, 2)]);
+        rows[0].value_sum = 7;
+        assert_eq!(
+            rules_on((0, 2), &(tiles.clone(), rows.clone(), cols.clone()), all_values()),
+            Err(Contradiction { tile: Some((0, 2)), rule: Rule::Rule3 }));
+
+        // a missing Voltorb plus leftover points can't both fit in one tile
+        rows[0] = SumData { value_sum: 4, voltorb_count: 1 };
+        assert_eq!(
+            rules_on((0, 2), &(tiles, rows, cols), all_values()),
+            Err(Contradiction { tile: Some((0, 2)), rule: Rule::Rule3 }));
+    }
+
+    #[test]
+    fn rule4_removes_twos_and_threes_from_lines_of_ones() {
+        let position = rule_position(&[&[1, 1, 66], &[2, 1, 1], &[66, 1, 3]], &[(0, 0), (0, 1), (0, 2)]);
+        let mut possible_values = HashMap::from([((0, 1), all_values())]);
+        let (tiles, rows, cols) = &position;
+        rule4((0, 1), tiles, rows, cols, &mut possible_values);
+        assert_eq!(possible_values[&(0, 1)], HashSet::from([TileValue::Voltorb, TileValue::One]));
+    }
+
+    #[test]
+    fn line_propagation_pins_values_single_rules_miss() {
+        // row 0 needs 6 more from two hidden tiles, only 3 + 3 works
+        let position = rule_position(&[&[3, 3, 1], &[1, 66, 1], &[2, 1, 1]], &[(0, 0), (0, 1), (1, 1), (2, 0)]);
+        let (tiles, rows, cols) = &position;
+        let mut possible_values: HashMap<(usize, usize), HashSet<TileValue>> = get_unflipped_tiles(tiles)
+            .into_iter()
+            .map(|tile| (tile, all_values()))
+            .collect();
+
+        for tile in get_unflipped_tiles(tiles) {
+            apply_rules(tile, tiles, rows, &get_row_sums(tiles), cols, &get_col_sums(tiles), &mut possible_values, None).unwrap();
+        }
+        assert!(possible_values[&(0, 0)].len() > 1);
+
+        assert_eq!(propagate_lines(tiles, rows, cols, &mut possible_values, None), Ok(true));
+        assert_eq!(possible_values[&(0, 0)], HashSet::from([TileValue::Three]));
+        assert_eq!(possible_values[&(0, 1)], HashSet::from([TileValue::Three]));
+        // column 0 then has 2 left for (2, 0), column 1 a Voltorb for (1, 1)
+        assert_eq!(possible_values[&(2, 0)], HashSet::from([TileValue::Two]));
+        assert_eq!(possible_values[&(1, 1)], HashSet::from([TileValue::Voltorb]));
+    }
+
+    #[test]
+    fn line_propagation_reports_unfillable_lines() {
+        let (tiles, mut rows, cols) = rule_position(&[&[1, 1, 1], &[1, 1, 1], &[1, 1, 1]], &[(0, 0), (0, 1)]);
+        // two tiles can't add up to 7
+        rows[0].value_sum = 8;
+        let mut possible_values = HashMap::from([((0, 0), all_values()), ((0, 1), all_values())]);
+        assert_eq!(
+            propagate_lines(&tiles, &rows, &cols, &mut possible_values, None),
+            Err(Contradiction { tile: Some((0, 0)), rule: Rule::Line }));
+    }
+
+    #[test]
+    fn explanations_include_the_deductions_they_build_on() {
+        let hidden: Vec<(usize, usize)> = (0..4).flat_map(|r| (0..4).map(move |c| (r, c))).collect();
+        let (tiles, rows, cols) = rule_position(&[&[3, 3, 2, 66], &[66, 2, 66, 1], &[1, 1, 1, 1], &[2, 1, 3, 1]], &hidden);
+        let explanation = explain_position(&tiles, &rows, &cols).unwrap();
+
+        // row 0 only rules out a Voltorb on (0,0) once (0,1) and (0,3) have been narrowed down
+        assert_eq!(explanation.lines(), [
+            "(0,0) is guaranteed safe:",
+            "  - column 1 has 0 Voltorbs left (rule1) so (0,1) cannot be a Voltorb",
+            "  - column 3 has no 2s or 3s left (rule4) so (0,3) cannot be a 2 or 3",
+            "  - row 0 needs 8 more from 4 hidden tiles with 1 Voltorbs left (line) so (0,0) cannot be a Voltorb or 1",
+        ]);
+
+        // a sure tile the rules never reach is credited to the search
+        let searched = Explanation { best_move: ((1, 2), 1.0), steps: Vec::new() };
+        assert!(searched.lines()[0].contains("found by exhaustive search"));
+    }
+
+    #[test]
+    fn contradiction_reaches_the_caller() {
+        let mut game = Game::new_seeded(3, 7, Some(1), Some(1), Some(2));
+        // an empty row can't hold points or Voltorbs
+        game.row_sums[0] = SumData { value_sum: 0, voltorb_count: 0 };
+        assert_eq!(try_optimized_solver(&mut game), Err(Contradiction { tile: Some((0, 0)), rule: Rule::Rule2 }));
+        assert_eq!(optimized_solver(&mut game), ((usize::MAX, usize::MAX), 0.0));
+    }
+}
@@ -0,0 +1,222 @@
+// Searches for boards that are as hard as possible for the optimized solver and keeps them as a corpus
+// Hard means the clues fit as many boards as possible, best of all when the rules can't prune a single value.
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::Path;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Serialize, Deserialize};
+use crate::board::{Board, PremadeBoard, SumData, TileValue};
+use crate::position::Clue;
+use crate::solver::{count_solutions, pruning_progress};
+
+pub const CORPUS_PATH: &str = "data/stress_corpus.json";
+// Longest the optimized solver may spend on any corpus board before it counts as a regression.
+// Every board took about 0.1s in release and 0.75s in debug when this was set, the rest is headroom.
+pub const SOLVE_BUDGET_MS: f64 = if cfg!(debug_assertions) { 4000.0 } else { 500.0 };
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StressBoard {
+    pub goal: StressGoal,
+    pub solution: Vec<Vec<u8>>,  // TileValue codes, 66 for a Voltorb
+    pub row_clues: Vec<Clue>,
+    pub col_clues: Vec<Clue>,
+    pub solution_count: usize,   // boards that fit the clues, capped at the search's solution_limit
+    pub values_pruned: usize,    // candidate values the rules remove from the blank board
+}
+
+// What a search climbs towards, the corpus mixes both kinds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StressGoal {
+    Solutions,   // as many boards fitting the clues as possible
+    Unprunable,  // as few values pruned as possible, ideally none
+}
+
+#[derive(Debug, Clone)]
+pub struct StressConfig {
+    pub board_dim: usize,
+    pub num_twos: usize,
+    pub num_threes: usize,
+    pub num_voltorbs: usize,
+    pub restarts: usize,         // fresh random boards to climb from, each one gives a corpus entry, goals alternate
+    pub steps: usize,            // tile swaps tried per restart
+    pub solution_limit: usize,   // stop counting here so one bad board can't stall the search
+    pub seed: u64,
+}
+
+impl Default for StressConfig {
+    // Voltorb-heavy 4x4, big enough to hurt and small enough to count
+    fn default() -> StressConfig {
+        StressConfig {
+            board_dim: 4,
+            num_twos: 3,
+            num_threes: 3,
+            num_voltorbs: 5,
+            restarts: 10,
+            steps: 200,
+            solution_limit: 100_000,
+            seed: 0,
+        }
+    }
+}
+
+impl StressBoard {
+    pub fn rate(board: &Board, goal: StressGoal, solution_limit: usize) -> StressBoard {
+        let rows = board.get_row_sums();
+        let cols = board.get_col_sums();
+        let blank = vec![vec![TileValue::Hidden; board.get_board_dim()]; board.get_board_dim()];
+
+        StressBoard {
+            goal,
+            solution: board.get_tiles().iter().map(|row| row.iter().map(|tile| tile.to_value()).collect()).collect(),
+            row_clues: clues(&rows),
+            col_clues: clues(&cols),
+            solution_count: count_solutions(&blank, &rows, &cols, solution_limit),
+            // the clues come from a real board so they can't contradict a blank one
+            values_pruned: pruning_progress(&blank, &rows, &cols).unwrap_or(0),
+        }
+    }
+
+    pub fn defeats_pruning(&self) -> bool {
+        self.values_pruned == 0
+    }
+
+    pub fn board(&self) -> Board {
+        Board::from_tiles(self.solution.iter().map(|row| row.iter().map(|&code| TileValue::to_enum(code)).collect()).collect())
+    }
+
+    // Ready to hand to Game::new with every tile still hidden
+    pub fn premade(&self) -> PremadeBoard {
+        let solution = self.board();
+        let initial = Board::new(solution.get_board_dim(), TileValue::Hidden);
+        PremadeBoard::new(solution, initial)
+    }
+
+    // Higher is harder, ties on the goal's own measure go to the other one
+    fn score(&self) -> (usize, usize) {
+        match self.goal {
+            StressGoal::Solutions => (self.solution_count, usize::MAX - self.values_pruned),
+            StressGoal::Unprunable => (usize::MAX - self.values_pruned, self.solution_count),
+        }
+    }
+}
+
+fn clues(sums: &[SumData]) -> Vec<Clue> {
+    sums.iter().map(|sum| Clue { sum: sum.value_sum, voltorbs: sum.voltorb_count }).collect()
+}
+
+// Hill climbs from random boards by swapping two different tiles, which keeps the tile counts fixed.
+// Returns the best board of every restart without duplicates, unprunable ones first then hardest first.
+pub fn generate_stress_boards(config: &StressConfig) -> Vec<StressBoard> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let dim = config.board_dim;
+    let mut corpus: Vec<StressBoard> = Vec::new();
+
+    for restart in 0..config.restarts {
+        let goal = if restart % 2 == 0 { StressGoal::Solutions } else { StressGoal::Unprunable };
+        let mut board = Board::new(dim, TileValue::Hidden);
+        board.create_solution_with_rng(&mut rng, Some(config.num_twos), Some(config.num_threes), Some(config.num_voltorbs))
+            .unwrap_or_else(|err| panic!("Failed to generate board: {}", err));
+        let mut current = StressBoard::rate(&board, goal, config.solution_limit);
+
+        for _ in 0..config.steps {
+            let a = (rng.random_range(0..dim), rng.random_range(0..dim));
+            let b = (rng.random_range(0..dim), rng.random_range(0..dim));
+            let (val_a, val_b) = (board.get_val(a.0, a.1), board.get_val(b.0, b.1));
+            if val_a == val_b {
+                continue;
+            }
+
+            board.set_val(a.0, a.1, val_b);
+            board.set_val(b.0, b.1, val_a);
+            let candidate = StressBoard::rate(&board, goal, config.solution_limit);
+            // sideways moves are fine, they let the search drift across plateaus
+            if candidate.score() >= current.score() {
+                current = candidate;
+            } else {
+                board.set_val(a.0, a.1, val_a);
+                board.set_val(b.0, b.1, val_b);
+            }
+        }
+
+        if !corpus.iter().any(|entry| entry.solution == current.solution) {
+            corpus.push(current);
+        }
+    }
+
+    corpus.sort_by_key(|entry| (entry.goal == StressGoal::Solutions, Reverse(entry.score())));
+    corpus
+}
+
+pub fn save_corpus(path: &Path, corpus: &[StressBoard]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(corpus).expect("Failed to serialize JSON");
+    fs::write(path, json)
+}
+
+pub fn load_corpus(path: &Path) -> io::Result<Vec<StressBoard>> {
+    let json = fs::read_to_string(path)?;
+    serde_json::from_str(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use crate::game::Game;
+    use crate::solver::optimized_solver;
+
+    #[test]
+    fn hand_made_unprunable_board_is_detected() {
+        let premade = &Board::premade_boards()[4];
+        let rated = StressBoard::rate(&premade.solution, StressGoal::Unprunable, 100_000);
+        assert!(rated.defeats_pruning());
+        assert!(rated.solution_count > 1);
+    }
+
+    #[test]
+    fn search_never_ends_worse_than_it_started() {
+        let config = StressConfig { board_dim: 3, num_twos: 1, num_threes: 1, num_voltorbs: 3, restarts: 3, steps: 40, ..StressConfig::default() };
+        let corpus = generate_stress_boards(&config);
+        assert_eq!(corpus, generate_stress_boards(&config));
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut first = Board::new(3, TileValue::Hidden);
+        first.create_solution_with_rng(&mut rng, Some(1), Some(1), Some(3)).unwrap();
+        let start = StressBoard::rate(&first, StressGoal::Solutions, config.solution_limit);
+        assert!(corpus[0].score() >= start.score());
+
+        for entry in &corpus {
+            let board = entry.board();
+            assert_eq!(entry.row_clues, clues(&board.get_row_sums()));
+            assert_eq!(entry.col_clues, clues(&board.get_col_sums()));
+            assert_eq!(board.get_tiles().iter().flatten().filter(|&&tile| tile == TileValue::Voltorb).count(), 3);
+        }
+    }
+
+    #[test]
+    fn optimized_solver_stays_within_budget_on_the_corpus() {
+        let corpus = load_corpus(Path::new(env!("CARGO_MANIFEST_DIR")).join(CORPUS_PATH).as_path()).unwrap();
+        for (index, entry) in corpus.iter().enumerate() {
+            let mut game = Game::new(entry.solution.len(), Some(entry.premade()), None, None, None);
+            let start = Instant::now();
+            optimized_solver(&mut game);
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            assert!(elapsed <= SOLVE_BUDGET_MS, "board {} took {:.0}ms, the budget is {:.0}ms", index, elapsed, SOLVE_BUDGET_MS);
+        }
+    }
+
+    // The checked-in corpus must still describe itself correctly, otherwise benchmarks compare different boards
+    #[test]
+    fn checked_in_corpus_is_consistent() {
+        let corpus = load_corpus(Path::new(env!("CARGO_MANIFEST_DIR")).join(CORPUS_PATH).as_path()).unwrap();
+        assert!(!corpus.is_empty());
+        for entry in &corpus {
+            let rerated = StressBoard::rate(&entry.board(), entry.goal, 100_000);
+            assert_eq!(&rerated, entry);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use crate::board::TileValue;
+use crate::cli::ADVICE_MAX_DIM;
+use crate::game::{Game, GameState};
+use crate::solver::explain_optimized_solver;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Char(char),
+}
+
+// Puts the terminal in raw mode for as long as it lives, restores it on drop even if we panic
+struct RawTerminal {
+    saved_state: String,
+}
+
+struct Screen {
+    game: Game,
+    board_dim: usize,
+    cursor: (usize, usize),
+    advice: Option<String>,  // worked out once when asked for and kept until the next flip
+    message: String,
+}
+
+impl RawTerminal {
+    fn enable() -> io::Result<RawTerminal> {
+        let saved_state = stty(&["-g"])?;
+        stty(&["raw", "-echo"])?;
+        // alternate screen so the shell history is left alone, and hide the cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(RawTerminal { saved_state: saved_state.trim().to_string() })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = stty(&[&self.saved_state]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed, is stdin a terminal?"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Full-screen game on the same Game API as the line prompt
+pub fn run_tui(game: Game) -> io::Result<()> {
+    let board_dim = game.curr_board.get_board_dim();
+    let mut screen = Screen {
+        game,
+        board_dim,
+        cursor: (0, 0),
+        advice: None,
+        message: String::from("Arrows move, Enter flips, 0-3 toggle memos, a toggles advice, q quits"),
+    };
+
+    let _terminal = RawTerminal::enable()?;
+    let mut stdin = io::stdin().lock();
+    let mut buf = [0u8; 16];
+
+    loop {
+        print!("{}", screen.render());
+        io::stdout().flush()?;
+
+        let bytes_read = stdin.read(&mut buf)?;
+        if bytes_read == 0 {
+            return Ok(());
+        }
+        for key in decode_keys(&buf[..bytes_read]) {
+            if !screen.handle(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Screen {
+    // false once the player wants out
+    fn handle(&mut self, key: Key) -> bool {
+        let last = self.board_dim - 1;
+        let (r, c) = self.cursor;
+        match key {
+            Key::Up => self.cursor.0 = r.saturating_sub(1),
+            Key::Down => self.cursor.0 = (r + 1).min(last),
+            Key::Left => self.cursor.1 = c.saturating_sub(1),
+            Key::Right => self.cursor.1 = (c + 1).min(last),
+            Key::Char('q') | Key::Char('Q') => return false,
+            _ if self.game.get_state() != GameState::InProgress => {}
+            Key::Enter => self.flip(),
+            Key::Char('a') | Key::Char('A') => self.toggle_advice(),
+            Key::Char(digit @ '0'..='3') => {
+                let val = if digit == '0' { TileValue::Voltorb } else { TileValue::to_enum(digit as u8 - b'0') };
+                self.game.toggle_memo(r, c, val);
+            }
+            Key::Char(_) => {}
+        }
+        true
+    }
+
+    fn flip(&mut self) {
+        let (r, c) = self.cursor;
+        if self.game.curr_board.get_val(r, c) != TileValue::Hidden {
+            self.message = format!("({},{}) is already flipped", r, c);
+            return;
+        }
+        self.message = match self.game.click(r, c) {
+            GameState::Won => String::from("Run it back double or nothing! Press q to leave"),
+            GameState::Lost => String::from("big boooooooom. Press q to leave"),
+            GameState::InProgress => format!("Flipped ({},{})", r, c),
+        };
+        // advice is for one position only, ask again to see the next one
+        self.advice = None;
+    }
+
+    // Runs the solver here rather than in render so holding down an arrow key doesn't re-solve every frame.
+    // Past ADVICE_MAX_DIM the solver can take most of a minute, so it isn't offered at all
+    fn toggle_advice(&mut self) {
+        if self.advice.take().is_some() {
+            return;
+        }
+        if self.board_dim > ADVICE_MAX_DIM {
+            self.message = format!("Advice is only available up to {}x{}", ADVICE_MAX_DIM, ADVICE_MAX_DIM);
+            return;
+        }
+        self.game.use_hint();
+        self.advice = Some(match explain_optimized_solver(&mut self.game) {
+            Ok(explanation) => {
+                let ((r, c), probability) = explanation.best_move;
+                format!("Solver: flip ({},{}), {:.0}% safe", r, c, probability * 100.0)
+            }
+            Err(contradiction) => format!("Solver: {}", contradiction),
+        });
+    }
+
+    // The whole frame, drawn from the top left and clearing each line so nothing scrolls
+    fn render(&self) -> String {
+        let mut out = String::from("\x1b[H");
+        let n = self.board_dim;
+
+        let mut header = String::from("      ");
+        for c in 0..n {
+            write!(header, " S:{:<2} ", self.game.col_sums[c].value_sum).unwrap();
+        }
+        line(&mut out, &header);
+        let mut header = String::from("      ");
+        for c in 0..n {
+            write!(header, " V:{:<2} ", self.game.col_sums[c].voltorb_count).unwrap();
+        }
+        line(&mut out, &header);
+
+        for r in 0..n {
+            let mut row = format!(" R{:<2} |", r);
+            for c in 0..n {
+                let cell = self.cell(r, c);
+                if (r, c) == self.cursor {
+                    write!(row, "\x1b[7m{}\x1b[0m", cell).unwrap();
+                } else {
+                    row.push_str(&cell);
+                }
+            }
+            write!(row, "| S:{:<2} V:{}", self.game.row_sums[r].value_sum, self.game.row_sums[r].voltorb_count).unwrap();
+            line(&mut out, &row);
+        }
+        line(&mut out, "");
+
+        let advice = self.advice.as_deref().unwrap_or("Solver: press a for advice");
+        let status = format!(
+            " Score: {}  Hints: {}  Cursor: ({},{})  {}",
+            self.game.get_score(), self.game.get_hints_used(), self.cursor.0, self.cursor.1, advice
+        );
+        line(&mut out, &format!("\x1b[7m{}\x1b[0m", status));
+        line(&mut out, &self.message);
+
+        // wipe whatever an older, longer frame left below us
+        out.push_str("\x1b[J");
+        out
+    }
+
+    // Every cell is 6 columns wide, hidden tiles show the player's memos
+    fn cell(&self, r: usize, c: usize) -> String {
+        match self.game.curr_board.get_val(r, c) {
+            TileValue::Hidden => {
+                let memos = self.game.get_memos(r, c);
+                let mut marks = String::new();
+                for val in [TileValue::Voltorb, TileValue::One, TileValue::Two, TileValue::Three] {
+                    let mark = match val {
+                        TileValue::Voltorb => 'V',
+                        other => (b'0' + other.to_value()) as char,
+                    };
+                    marks.push(if memos.contains(&val) { mark } else { '.' });
+                }
+                format!("\x1b[32m {} \x1b[0m", marks)
+            }
+            TileValue::Voltorb => String::from("\x1b[31m  ()  \x1b[0m"),
+            TileValue::ERR => String::from("  ??  "),
+            val => format!("  {}   ", val.to_value()),
+        }
+    }
+}
+
+// raw mode needs \r before every \n, and \x1b[K clears what the last frame had on this line
+fn line(out: &mut String, text: &str) {
+    out.push_str(text);
+    out.push_str("\x1b[K\r\n");
+}
+
+// One read can hold several keys when the player types fast
+fn decode_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b if bytes.get(i + 1) == Some(&b'[') && i + 2 < bytes.len() => {
+                match bytes[i + 2] {
+                    b'A' => keys.push(Key::Up),
+                    b'B' => keys.push(Key::Down),
+                    b'C' => keys.push(Key::Right),
+                    b'D' => keys.push(Key::Left),
+                    _ => {}
+                }
+                i += 3;
+                continue;
+            }
+            b'\r' | b'\n' => keys.push(Key::Enter),
+            // ctrl-c doesn't raise SIGINT in raw mode
+            0x03 => keys.push(Key::Char('q')),
+            byte if byte.is_ascii_graphic() || byte == b' ' => keys.push(Key::Char(byte as char)),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(board_dim: usize) -> Screen {
+        Screen {
+            game: Game::new_seeded(board_dim, 7, None, None, None),
+            board_dim,
+            cursor: (0, 0),
+            advice: None,
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn decodes_arrows_enter_and_letters_from_one_read() {
+        let keys = decode_keys(b"\x1b[A\x1b[C\r2q");
+        assert_eq!(keys, vec![Key::Up, Key::Right, Key::Enter, Key::Char('2'), Key::Char('q')]);
+    }
+
+    #[test]
+    fn cursor_stays_on_the_board() {
+        let mut screen = screen(3);
+        for key in [Key::Up, Key::Left, Key::Down, Key::Down, Key::Down, Key::Right, Key::Right, Key::Right] {
+            assert!(screen.handle(key));
+        }
+        assert_eq!(screen.cursor, (2, 2));
+    }
+
+    #[test]
+    fn memos_toggle_on_hidden_tiles() {
+        let mut screen = screen(3);
+        screen.handle(Key::Char('0'));
+        screen.handle(Key::Char('3'));
+        screen.handle(Key::Char('3'));
+        assert_eq!(screen.game.get_memos(0, 0), vec![TileValue::Voltorb]);
+        assert!(screen.render().contains("V..."));
+    }
+
+    #[test]
+    fn advice_is_solved_once_until_the_next_flip() {
+        let mut screen = screen(3);
+        screen.handle(Key::Char('a'));
+        let advice = screen.advice.clone().unwrap();
+        assert!(advice.starts_with("Solver: flip"), "{}", advice);
+        screen.handle(Key::Right);
+        assert!(screen.render().contains(&advice));
+        assert_eq!(screen.game.get_hints_used(), 1);
+
+        screen.handle(Key::Enter);
+        assert!(screen.advice.is_none());
+    }
+
+    #[test]
+    fn advice_is_not_offered_on_big_boards() {
+        let mut screen = screen(ADVICE_MAX_DIM + 1);
+        screen.handle(Key::Char('a'));
+        assert!(screen.advice.is_none());
+        assert_eq!(screen.game.get_hints_used(), 0);
+        assert!(screen.message.contains("only available up to"));
+    }
+}
@@ -0,0 +1,88 @@
+// Raw exports for wasm32-unknown-unknown, no wasm-bindgen so the page does the string passing itself:
+//   const ptr = vf_alloc(bytes.length); copy the request JSON in; const out = vf_probability_grid(ptr, bytes.length);
+//   read the u32 little-endian length at out, then that many bytes of response JSON; vf_free both buffers.
+// The request is a Position ({"board", "row_clues", "col_clues"}) and the response an Analysis or {"error": "..."}.
+use std::ptr;
+use serde::Serialize;
+use crate::position::Position;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+// Scratch space the host can write a request into
+#[no_mangle]
+pub extern "C" fn vf_alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// # Safety
+/// `ptr` and `len` must come from `vf_alloc`, or from `vf_probability_grid` with `len` being the length prefix plus 4.
+#[no_mangle]
+pub unsafe extern "C" fn vf_free(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+/// # Safety
+/// `ptr` must point to `len` readable bytes, normally a buffer from `vf_alloc`.
+#[no_mangle]
+pub unsafe extern "C" fn vf_probability_grid(ptr: *const u8, len: usize) -> *mut u8 {
+    let request = std::slice::from_raw_parts(ptr, len);
+    let response = probability_grid_json(request);
+
+    let mut out = Vec::with_capacity(response.len() + 4);
+    out.extend_from_slice(&(response.len() as u32).to_le_bytes());
+    out.extend_from_slice(response.as_bytes());
+    Box::into_raw(out.into_boxed_slice()) as *mut u8
+}
+
+// Everything but the pointer juggling, so it can be tested natively
+pub fn probability_grid_json(request: &[u8]) -> String {
+    let result = serde_json::from_slice::<Position>(request)
+        .map_err(|err| format!("bad position: {}", err))
+        .and_then(|position| position.analyze().map_err(|err| err.to_string()));
+
+    match result {
+        Ok(analysis) => serde_json::to_string(&analysis),
+        Err(error) => serde_json::to_string(&ErrorResponse { error }),
+    }
+    .expect("Failed to serialize JSON")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const POSITION: &str = r#"{
+        "board": [["?", "?", "?"], ["?", "?", "?"], ["?", "?", "?"]],
+        "row_clues": [{"sum": 3, "voltorbs": 1}, {"sum": 5, "voltorbs": 0}, {"sum": 3, "voltorbs": 0}],
+        "col_clues": [{"sum": 2, "voltorbs": 1}, {"sum": 5, "voltorbs": 0}, {"sum": 4, "voltorbs": 0}]
+    }"#;
+
+    #[test]
+    fn round_trip_through_exported_buffers() {
+        unsafe {
+            let input = vf_alloc(POSITION.len());
+            ptr::copy_nonoverlapping(POSITION.as_ptr(), input, POSITION.len());
+            let output = vf_probability_grid(input, POSITION.len());
+            vf_free(input, POSITION.len());
+
+            let len = u32::from_le_bytes(std::slice::from_raw_parts(output, 4).try_into().unwrap()) as usize;
+            let response: Value = serde_json::from_slice(std::slice::from_raw_parts(output.add(4), len)).unwrap();
+            vf_free(output, len + 4);
+
+            assert_eq!(response["probability"], 1.0);
+            assert_eq!(response["safety"][0][0], 0.0);
+        }
+    }
+
+    #[test]
+    fn malformed_positions_report_errors() {
+        let response: Value = serde_json::from_str(&probability_grid_json(b"{\"board\": []}")).unwrap();
+        assert!(response["error"].as_str().unwrap().starts_with("bad position"));
+    }
+}
@@ -0,0 +1,8 @@
+#!/bin/sh
+# Builds the cdylib with only the ffi feature, compiles test_ffi.c against it and runs it
+set -e
+cd "$(dirname "$0")/../.."
+
+cargo build --lib --no-default-features --features ffi
+cc -Wall -Wextra -o target/debug/test_ffi tests/ffi/test_ffi.c -Iinclude -Ltarget/debug -lvoltorb_flip
+LD_LIBRARY_PATH=target/debug DYLD_LIBRARY_PATH=target/debug ./target/debug/test_ffi
@@ -0,0 +1,52 @@
+/* Links against the cdylib and checks the C API end to end, run with tests/ffi/run.sh */
+#include <stdio.h>
+#include <stdlib.h>
+#include "voltorb_flip.h"
+
+#define CHECK(cond) do { if (!(cond)) { fprintf(stderr, "FAIL %s:%d: %s\n", __FILE__, __LINE__, #cond); exit(1); } } while (0)
+
+int main(void) {
+    /* 3x3 board, (0,0) is the only Voltorb */
+    VfClue rows[3] = {{3, 1}, {5, 0}, {3, 0}};
+    VfClue cols[3] = {{2, 1}, {5, 0}, {4, 0}};
+    uint8_t tiles[9] = {
+        VF_HIDDEN, VF_HIDDEN, VF_HIDDEN,
+        VF_HIDDEN, VF_HIDDEN, VF_HIDDEN,
+        VF_HIDDEN, VF_HIDDEN, VF_HIDDEN,
+    };
+
+    VfPosition *position = vf_position_new(3, rows, cols, tiles);
+    CHECK(position != NULL);
+    VfResult *result = vf_solve(position);
+    CHECK(result != NULL);
+    CHECK(vf_result_size(result) == 3);
+    CHECK(vf_result_probability(result, 0, 0) == 0.0f);
+
+    size_t row, col;
+    float probability;
+    CHECK(vf_result_best_move(result, &row, &col, &probability) == 0);
+    CHECK(probability == 1.0f);
+    printf("best move (%zu,%zu) safe with probability %.2f\n", row, col, probability);
+
+    for (size_t r = 0; r < 3; r++) {
+        for (size_t c = 0; c < 3; c++) {
+            printf("%6.2f", vf_result_probability(result, r, c));
+        }
+        printf("\n");
+    }
+    vf_result_free(result);
+    vf_position_free(position);
+
+    /* flipping a Voltorb where the row has none left contradicts the clues */
+    tiles[3] = VF_VOLTORB;
+    position = vf_position_new(3, rows, cols, tiles);
+    CHECK(position != NULL);
+    CHECK(vf_solve(position) == NULL);
+    vf_position_free(position);
+
+    tiles[3] = 9;
+    CHECK(vf_position_new(3, rows, cols, tiles) == NULL);
+
+    printf("ok\n");
+    return 0;
+}